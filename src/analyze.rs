@@ -1,9 +1,1029 @@
 use crate::{
-    error::Result,
-    models::{AscentDB, Count},
+    error::{Error, Result, User},
+    grades::{self, GradeScale},
+    models::{Ascent, AscentDB, AscentFilter, Count, Crosstab, Period, ReportFormat, Style},
+    utils,
 };
+use serde::Serialize;
+use std::collections::HashMap;
+use time::{Date, Month, Weekday};
 
-fn make_counts_table(counts: Vec<Count>) -> String {
+/// Analysis of the ascents in a database, which can be rendered in any
+/// report format
+#[derive(Debug, Serialize)]
+pub struct Report {
+    database: String,
+    total_count: u32,
+    unique_route_count: u32,
+    year_counts: Vec<Count>,
+    crag_counts: Vec<Count>,
+    style_counts: Vec<Count>,
+    month_counts: Vec<Count>,
+    weekday_counts: Vec<Count>,
+    calendar: Option<Calendar>,
+    grade_counts: Vec<GradeCounts>,
+    pyramids: Vec<Pyramid>,
+    progressions: Vec<Progression>,
+    hardest_ascents: Vec<HardestAscent>,
+}
+
+/// Options for what goes into an analysis of ascents
+#[derive(Debug, Default)]
+pub struct AnalyzeOptions {
+    // Number of grades in each grade pyramid, from the hardest one down
+    pub pyramid_grades: Option<usize>,
+    // Period to show the progression of grades over
+    pub period: Period,
+    // Year to show a calendar of ascents for
+    pub calendar_year: Option<i32>,
+}
+
+// Counts of ascents by grade in one scale, where converted counts include
+// every scale of the same discipline
+#[derive(Debug, Serialize)]
+struct GradeCounts {
+    discipline: &'static str,
+    scale: &'static str,
+    converted: bool,
+    counts: Vec<Count>,
+}
+
+// Grade pyramid of the clean leads in one scale, from the hardest grade
+// down to the easiest with every grade in between, where a grade is thin if
+// it has less than half as many ascents as the grade below it
+#[derive(Debug, Serialize)]
+struct Pyramid {
+    discipline: &'static str,
+    scale: &'static str,
+    converted: bool,
+    levels: Vec<PyramidLevel>,
+}
+
+#[derive(Debug, Serialize)]
+struct PyramidLevel {
+    grade: String,
+    count: u32,
+    thin: bool,
+}
+
+// Hardest and median grade of the ascents in one scale over time
+#[derive(Debug, Serialize)]
+struct Progression {
+    discipline: &'static str,
+    scale: &'static str,
+    converted: bool,
+    period: &'static str,
+    periods: Vec<ProgressionPeriod>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProgressionPeriod {
+    period: String,
+    count: u32,
+    hardest: String,
+    median: String,
+    // Order of the hardest grade within its scale, for drawing sparklines
+    #[serde(skip)]
+    hardest_order: u32,
+}
+
+// Bars of a sparkline, from lowest to highest
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Ascents on each day of one year, drawn with a column for each week and a
+// row for each day of the week
+#[derive(Debug, Serialize)]
+struct Calendar {
+    year: i32,
+    day_counts: Vec<Count>,
+}
+
+// Shades of activity, from none to the most
+const SHADES: [char; 5] = ['.', ':', '+', '*', '#'];
+
+/// Counts of ascents by year for each of a set of rows, such as crags, with
+/// the total of every row and column
+#[derive(Debug, Serialize)]
+pub struct CrosstabTable {
+    title: String,
+    // Scale of the grades, for tables with rows of grades
+    scale: Option<&'static str>,
+    row_header: &'static str,
+    years: Vec<String>,
+    rows: Vec<CrosstabRow>,
+    totals: Vec<u32>,
+    total: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct CrosstabRow {
+    label: String,
+    counts: Vec<u32>,
+    total: u32,
+}
+
+// Widest a bar of a grade pyramid can be, which is odd so that every bar
+// can be centered
+const PYRAMID_WIDTH: u32 = 41;
+
+// Hardest ascent in one scale, either overall or of one style, along with
+// its grade in that scale
+#[derive(Debug, Serialize)]
+struct HardestAscent {
+    discipline: &'static str,
+    scale: &'static str,
+    converted: bool,
+    style: Option<Style>,
+    grade: String,
+    ascent: Ascent,
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn scale_label(scale: &str) -> &'static str {
+    grades::find_scale(scale)
+        .expect("Reported scale should be known")
+        .label()
+}
+
+impl GradeCounts {
+    fn title(&self) -> String {
+        let label = scale_label(self.scale);
+
+        if self.converted {
+            format!(
+                "Count of {} ascents by grade (converted to {label})",
+                self.discipline
+            )
+        } else {
+            format!("Count of {} ascents by grade ({label})", self.discipline)
+        }
+    }
+}
+
+impl Pyramid {
+    // Takes the lead counts of every grade from the easiest, so that grades
+    // without any leads count as thin
+    fn new(grade_counts: &GradeCounts, counts: Vec<Count>, grades: Option<usize>) -> Self {
+        let mut levels: Vec<PyramidLevel> = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, count)| PyramidLevel {
+                grade: count.category().clone(),
+                count: count.value(),
+                thin: i > 0 && count.value() * 2 < counts[i - 1].value(),
+            })
+            .collect();
+
+        if let Some(grades) = grades {
+            levels.truncate(grades);
+        }
+
+        Pyramid {
+            discipline: grade_counts.discipline,
+            scale: grade_counts.scale,
+            converted: grade_counts.converted,
+            levels,
+        }
+    }
+
+    fn title(&self) -> String {
+        let label = scale_label(self.scale);
+
+        if self.converted {
+            format!(
+                "Grade pyramid of {} ascents (converted to {label})",
+                self.discipline
+            )
+        } else {
+            format!("Grade pyramid of {} ascents ({label})", self.discipline)
+        }
+    }
+
+    // Draws the pyramid as a bar of #s for each grade, centered above one
+    // another, with one # for each ascent unless that's too wide to fit
+    fn draw(&self) -> String {
+        let max_count = self
+            .levels
+            .iter()
+            .map(|level| level.count)
+            .max()
+            .unwrap_or(1);
+        let grade_width = self
+            .levels
+            .iter()
+            .map(|level| level.grade.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let bar_width = |count: u32| -> usize {
+            if count == 0 {
+                return 0;
+            }
+
+            let width = if max_count * 2 - 1 <= PYRAMID_WIDTH {
+                count * 2 - 1
+            } else {
+                (count * PYRAMID_WIDTH).div_ceil(max_count)
+            };
+
+            if width % 2 == 0 {
+                width as usize - 1
+            } else {
+                width as usize
+            }
+        };
+        let width = bar_width(max_count);
+
+        let lines: Vec<String> = self
+            .levels
+            .iter()
+            .map(|level| {
+                let bar = "#".repeat(bar_width(level.count));
+                let thin = if level.thin { "  thin" } else { "" };
+
+                format!(
+                    "{:>grade_width$}  {bar:^width$}  {}{thin}",
+                    level.grade, level.count,
+                )
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+}
+
+impl Progression {
+    // Groups the grades of each period, which are in order from the easiest
+    // to the hardest, taking the lower of the middle two as the median of
+    // an even number of grades
+    fn new(
+        grade_counts: &GradeCounts,
+        period: Period,
+        period_grades: Vec<(String, String, u32)>,
+    ) -> Self {
+        let mut periods: Vec<ProgressionPeriod> = Vec::new();
+        let mut grades: Vec<(String, u32)> = Vec::new();
+
+        let mut period_grades = period_grades.into_iter().peekable();
+
+        while let Some((period, grade, order)) = period_grades.next() {
+            grades.push((grade, order));
+
+            if period_grades
+                .peek()
+                .is_some_and(|(next_period, _, _)| *next_period == period)
+            {
+                continue;
+            }
+
+            let (hardest, hardest_order) = grades.last().cloned().expect("Period has grades");
+
+            periods.push(ProgressionPeriod {
+                period,
+                count: grades.len() as u32,
+                hardest,
+                median: grades[(grades.len() - 1) / 2].0.clone(),
+                hardest_order,
+            });
+
+            grades.clear();
+        }
+
+        Progression {
+            discipline: grade_counts.discipline,
+            scale: grade_counts.scale,
+            converted: grade_counts.converted,
+            period: period.name(),
+            periods,
+        }
+    }
+
+    fn title(&self) -> String {
+        let label = scale_label(self.scale);
+
+        if self.converted {
+            format!(
+                "Progression of {} ascents by {} (converted to {label})",
+                self.discipline, self.period,
+            )
+        } else {
+            format!(
+                "Progression of {} ascents by {} ({label})",
+                self.discipline, self.period,
+            )
+        }
+    }
+
+    fn headers(&self) -> [String; 4] {
+        [
+            capitalize(self.period),
+            "Count".into(),
+            "Hardest".into(),
+            "Median".into(),
+        ]
+    }
+
+    fn rows(&self) -> Vec<[String; 4]> {
+        self.periods
+            .iter()
+            .map(|period| {
+                [
+                    period.period.clone(),
+                    period.count.to_string(),
+                    period.hardest.clone(),
+                    period.median.clone(),
+                ]
+            })
+            .collect()
+    }
+
+    // Sparkline of the hardest grade of each period, relative to the
+    // easiest and hardest of them
+    fn sparkline(&self) -> String {
+        let orders = self.periods.iter().map(|period| period.hardest_order);
+        let (Some(min), Some(max)) = (orders.clone().min(), orders.max()) else {
+            return String::new();
+        };
+
+        self.periods
+            .iter()
+            .map(|period| {
+                // Rounded to the nearest bar, so small steps up still show
+                let spark = match max - min {
+                    0 => 0,
+                    range => ((period.hardest_order - min) * 14 + range) / (2 * range),
+                };
+
+                SPARKS[spark as usize]
+            })
+            .collect()
+    }
+
+    fn draw(&self) -> String {
+        let headers = self.headers();
+        let rows = self.rows();
+
+        let mut widths = headers.clone().map(|header| header.chars().count());
+
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let make_line = |cells: &[String; 4]| -> String {
+            let [period, count, hardest, median] = cells;
+            let [period_width, count_width, hardest_width, _] = widths;
+
+            format!(
+                "{period:<period_width$}  {count:>count_width$}  {hardest:<hardest_width$}  {median}"
+            )
+            .trim_end()
+            .to_string()
+        };
+
+        let mut lines = vec![make_line(&headers)];
+        lines.extend(rows.iter().map(make_line));
+        lines.push(format!(
+            "Hardest grade by {}: {}",
+            self.period,
+            self.sparkline()
+        ));
+
+        lines.join("\n")
+    }
+}
+
+impl CrosstabTable {
+    // Adds up counts by row and year, with rows in the order they first
+    // appear in the counts
+    fn new(
+        title: String,
+        scale: Option<&'static str>,
+        row_header: &'static str,
+        years: &[String],
+        counts: Vec<(String, String, u32)>,
+    ) -> Self {
+        let mut rows: Vec<CrosstabRow> = Vec::new();
+
+        for (label, year, count) in counts {
+            let column = years
+                .iter()
+                .position(|y| *y == year)
+                .expect("Year should have a column");
+
+            if rows.last().is_none_or(|row| row.label != label) {
+                rows.push(CrosstabRow {
+                    label,
+                    counts: vec![0; years.len()],
+                    total: 0,
+                });
+            }
+
+            let row = rows.last_mut().expect("Row was just added");
+            row.counts[column] += count;
+            row.total += count;
+        }
+
+        let totals: Vec<u32> = (0..years.len())
+            .map(|column| rows.iter().map(|row| row.counts[column]).sum())
+            .collect();
+
+        CrosstabTable {
+            title,
+            scale,
+            row_header,
+            years: years.to_vec(),
+            total: totals.iter().sum(),
+            rows,
+            totals,
+        }
+    }
+
+    // Cells of the header, every row and the totals, leaving out the scale
+    fn cells(&self) -> Vec<Vec<String>> {
+        let mut header = vec![self.row_header.to_string()];
+        header.extend(self.years.iter().cloned());
+        header.push("total".to_string());
+
+        let mut cells = vec![header];
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| (row.label.as_str(), &row.counts, row.total))
+            .chain([("Total", &self.totals, self.total)]);
+
+        for (label, counts, total) in rows {
+            let mut line = vec![label.to_string()];
+            line.extend(counts.iter().map(u32::to_string));
+            line.push(total.to_string());
+            cells.push(line);
+        }
+
+        cells
+    }
+
+    fn draw(&self) -> String {
+        let mut cells = self.cells();
+        cells[0] = cells[0].iter().map(|cell| capitalize(cell)).collect();
+
+        let columns = cells[0].len();
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                cells
+                    .iter()
+                    .map(|line| line[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let lines: Vec<String> = cells
+            .iter()
+            .map(|line| {
+                let padded: Vec<String> = line
+                    .iter()
+                    .zip(&widths)
+                    .enumerate()
+                    .map(|(column, (cell, &width))| match column {
+                        0 => format!("{cell:<width$}"),
+                        _ => format!("{cell:>width$}"),
+                    })
+                    .collect();
+
+                padded.join("  ")
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+}
+
+impl Calendar {
+    fn title(&self) -> String {
+        format!("Calendar of ascents in {}", self.year)
+    }
+
+    fn draw(&self) -> String {
+        let first_day = Date::from_calendar_date(self.year, Month::January, 1)
+            .expect("Calendar year should be valid");
+        let day_counts: HashMap<&String, u32> = self
+            .day_counts
+            .iter()
+            .map(|count| (count.category(), count.value()))
+            .collect();
+        let max = self.day_counts.iter().map(Count::value).max().unwrap_or(0);
+
+        // Days before the first Monday go at the top of the first week
+        let offset = first_day.weekday().number_days_from_monday() as usize;
+        let days = time::util::days_in_year(self.year) as usize;
+        let weeks = (offset + days).div_ceil(7);
+
+        let mut labels = vec![' '; weeks];
+        let mut grid = vec![vec![' '; weeks]; 7];
+        let mut date = first_day;
+
+        for cell in offset..offset + days {
+            let (week, weekday) = (cell / 7, cell % 7);
+            let count = day_counts.get(&format_date(date)).copied().unwrap_or(0);
+
+            grid[weekday][week] = shade(count, max);
+
+            // Each month is labeled at the week of its first day, as long as
+            // the label of the month before is out of the way
+            if date.day() == 1
+                && labels[week.saturating_sub(1)..week]
+                    .iter()
+                    .all(|c| *c == ' ')
+            {
+                let label: Vec<char> = date.month().to_string().chars().take(3).collect();
+
+                labels.resize(labels.len().max(week + label.len()), ' ');
+                labels[week..week + label.len()].copy_from_slice(&label);
+            }
+
+            date = date.next_day().unwrap_or(date);
+        }
+
+        let mut lines = vec![format!("    {}", labels.iter().collect::<String>())];
+        let mut weekday = Weekday::Monday;
+
+        for row in grid {
+            let name: String = weekday.to_string().chars().take(3).collect();
+            lines.push(format!("{name} {}", row.iter().collect::<String>()));
+            weekday = weekday.next();
+        }
+
+        lines.push(format!(
+            "Less {} More, up to {max} ascent(s) in a day",
+            SHADES.iter().collect::<String>()
+        ));
+
+        lines
+            .iter()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Shade of a count of ascents, relative to the most of any count
+fn shade(count: u32, max: u32) -> char {
+    match count {
+        0 => SHADES[0],
+        count => SHADES[(count * 4).div_ceil(max) as usize],
+    }
+}
+
+// Line of shades for a set of counts, such as every month of the year
+fn heat_strip(counts: &[Count]) -> String {
+    let max = counts.iter().map(Count::value).max().unwrap_or(0);
+
+    counts
+        .iter()
+        .map(|count| shade(count.value(), max))
+        .collect()
+}
+
+fn format_date(date: Date) -> String {
+    date.format(utils::DATE_FORMAT)
+        .expect("Should be able to format date")
+}
+
+// Counts of every month of the year from January, including months without
+// any ascents, from counts by month number
+fn every_month(counts: Vec<Count>) -> Vec<Count> {
+    let mut month = Month::January;
+
+    (0..12)
+        .map(|_| {
+            let key = format!("{:02}", month as u8);
+            let count = Count::new(month.to_string(), find_count(&counts, &key));
+
+            month = month.next();
+            count
+        })
+        .collect()
+}
+
+// Counts of every day of the week from Monday, including days without any
+// ascents, from counts by day number from Sunday
+fn every_weekday(counts: Vec<Count>) -> Vec<Count> {
+    let mut weekday = Weekday::Monday;
+
+    (0..7)
+        .map(|_| {
+            let key = weekday.number_days_from_sunday().to_string();
+            let count = Count::new(weekday.to_string(), find_count(&counts, &key));
+
+            weekday = weekday.next();
+            count
+        })
+        .collect()
+}
+
+fn find_count(counts: &[Count], category: &str) -> u32 {
+    counts
+        .iter()
+        .find(|count| count.category() == category)
+        .map_or(0, Count::value)
+}
+
+impl HardestAscent {
+    // Which scales the ascent is the hardest of, such as "YDS onsight"
+    fn scales(&self) -> String {
+        let scales = if self.converted {
+            format!("All {} scales", self.discipline)
+        } else {
+            scale_label(self.scale).to_string()
+        };
+
+        match self.style {
+            Some(style) => format!("{scales} {style}"),
+            None => scales,
+        }
+    }
+
+    // Only mentions the converted grade of ascents logged in another scale
+    fn description(&self) -> String {
+        if self.converted && self.ascent.route().scale() != self.scale {
+            format!("{}, converted to {}", self.ascent, self.grade)
+        } else {
+            self.ascent.to_string()
+        }
+    }
+}
+
+/// Analyzes the ascents that meet a filter. Grades of the same discipline as
+/// the scale of the filter are converted to it, if one is given, while
+/// grades of other disciplines are still reported by scale.
+pub fn analyze_ascent_db(
+    database: &String,
+    filter: &AscentFilter,
+    options: &AnalyzeOptions,
+) -> Result<Report> {
+    let display_scale = find_display_scale(filter)?;
+    let calendar_filter = options
+        .calendar_year
+        .map(|year| calendar_filter(filter, year))
+        .transpose()?;
+    let db = AscentDB::new(database)?;
+
+    let mut report = Report {
+        database: database.clone(),
+        total_count: db.total_count(filter)?,
+        unique_route_count: db.unique_route_count(filter)?,
+        year_counts: db.year_counts(filter)?,
+        crag_counts: db.crag_counts(filter)?,
+        style_counts: db.style_counts(filter)?,
+        month_counts: every_month(db.month_counts(filter)?),
+        weekday_counts: every_weekday(db.weekday_counts(filter)?),
+        calendar: None,
+        grade_counts: Vec::new(),
+        pyramids: Vec::new(),
+        progressions: Vec::new(),
+        hardest_ascents: Vec::new(),
+    };
+
+    if let Some(calendar_filter) = calendar_filter {
+        report.calendar = Some(Calendar {
+            year: calendar_filter
+                .year
+                .expect("Calendar filter should have a year"),
+            day_counts: db.date_counts(&calendar_filter)?,
+        });
+    }
+
+    let mut hardest_ascents_by_style = Vec::new();
+
+    if let Some(display_scale) = display_scale {
+        let grade_counts = db.normalized_grade_counts(filter, display_scale.name())?;
+        let discipline = display_scale.discipline().label();
+
+        if !grade_counts.is_empty() {
+            let grade_counts = GradeCounts {
+                discipline,
+                scale: display_scale.name(),
+                converted: true,
+                counts: grade_counts,
+            };
+
+            let period_grades =
+                db.normalized_period_grades(filter, display_scale.name(), options.period)?;
+            report.progressions.push(Progression::new(
+                &grade_counts,
+                options.period,
+                period_grades,
+            ));
+
+            report.grade_counts.push(grade_counts);
+        }
+
+        for style in [None].into_iter().chain(Style::ALL.map(Some)) {
+            if let Some((ascent, grade)) =
+                db.normalized_hardest_ascent(filter, display_scale.name(), style)?
+            {
+                let hardest_ascent = HardestAscent {
+                    discipline,
+                    scale: display_scale.name(),
+                    converted: true,
+                    style,
+                    grade,
+                    ascent,
+                };
+
+                match style {
+                    Some(_) => hardest_ascents_by_style.push(hardest_ascent),
+                    None => report.hardest_ascents.push(hardest_ascent),
+                }
+            }
+        }
+    }
+
+    for scale in db.scales(filter)? {
+        let grade_scale = grades::find_scale(&scale).expect("Logged scale should be known");
+
+        if display_scale
+            .is_some_and(|display_scale| display_scale.discipline() == grade_scale.discipline())
+        {
+            continue;
+        }
+
+        let discipline = grade_scale.discipline().label();
+
+        let grade_counts = GradeCounts {
+            discipline,
+            scale: grade_scale.name(),
+            converted: false,
+            counts: db.grade_counts(filter, &scale)?,
+        };
+
+        let period_grades = db.period_grades(filter, &scale, options.period)?;
+        report.progressions.push(Progression::new(
+            &grade_counts,
+            options.period,
+            period_grades,
+        ));
+
+        report.grade_counts.push(grade_counts);
+
+        for style in [None].into_iter().chain(Style::ALL.map(Some)) {
+            if let Some(ascent) = db.hardest_ascent(filter, &scale, style)? {
+                let hardest_ascent = HardestAscent {
+                    discipline,
+                    scale: grade_scale.name(),
+                    converted: false,
+                    style,
+                    grade: ascent.route().grade().clone(),
+                    ascent,
+                };
+
+                match style {
+                    Some(_) => hardest_ascents_by_style.push(hardest_ascent),
+                    None => report.hardest_ascents.push(hardest_ascent),
+                }
+            }
+        }
+    }
+
+    // Overall hardest ascents come before the hardest of each style
+    report.hardest_ascents.append(&mut hardest_ascents_by_style);
+
+    for grade_counts in &report.grade_counts {
+        let counts = if grade_counts.converted {
+            db.normalized_pyramid_counts(filter, grade_counts.scale)?
+        } else {
+            db.pyramid_counts(filter, grade_counts.scale)?
+        };
+
+        // Scales with nothing but topropes have no pyramid
+        if !counts.is_empty() {
+            report
+                .pyramids
+                .push(Pyramid::new(grade_counts, counts, options.pyramid_grades));
+        }
+    }
+
+    Ok(report)
+}
+
+// Filter for the ascents in a calendar year, which can't be any other year
+// than the one the filter is already limited to
+fn calendar_filter(filter: &AscentFilter, year: i32) -> Result<AscentFilter> {
+    if Date::from_calendar_date(year, Month::January, 1).is_err() {
+        return Err(Error::User(User::InvalidYear));
+    }
+
+    if filter.year.is_some_and(|filter_year| filter_year != year) {
+        return Err(Error::User(User::CalendarYearMismatch));
+    }
+
+    Ok(AscentFilter {
+        year: Some(year),
+        ..filter.clone()
+    })
+}
+
+// Scale of the filter, if given, which grades of the same discipline are
+// converted to
+fn find_display_scale(filter: &AscentFilter) -> Result<Option<&'static dyn GradeScale>> {
+    match &filter.scale {
+        Some(name) => match grades::find_scale(name) {
+            Some(display_scale) => Ok(Some(display_scale)),
+            None => Err(Error::User(User::InvalidScale)),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Cross-tabulates the ascents that meet a filter by year, with rows of
+/// either crags or grades, where grades get a table for each scale like
+/// the grade counts of an analysis
+pub fn crosstab_ascent_db(
+    database: &String,
+    filter: &AscentFilter,
+    crosstab: Crosstab,
+) -> Result<Vec<CrosstabTable>> {
+    let display_scale = find_display_scale(filter)?;
+    let db = AscentDB::new(database)?;
+
+    // Every table has a column for every year, so that they line up
+    let years: Vec<String> = db
+        .year_counts(filter)?
+        .into_iter()
+        .map(|count| count.category().clone())
+        .collect();
+
+    if crosstab == Crosstab::Crag {
+        let table = CrosstabTable::new(
+            "Count of ascents by crag and year".to_string(),
+            None,
+            "crag",
+            &years,
+            db.crag_year_counts(filter)?,
+        );
+
+        return Ok(vec![table]);
+    }
+
+    // Grades come ordered by period, so reorder them from easiest to hardest
+    let grade_year_counts = |mut period_grades: Vec<(String, String, u32)>| {
+        period_grades.sort_by_key(|(_, _, order)| *order);
+        period_grades
+            .into_iter()
+            .map(|(year, grade, _)| (grade, year, 1))
+            .collect()
+    };
+
+    let mut tables = Vec::new();
+
+    if let Some(display_scale) = display_scale {
+        let period_grades =
+            db.normalized_period_grades(filter, display_scale.name(), Period::Year)?;
+
+        if !period_grades.is_empty() {
+            tables.push(CrosstabTable::new(
+                format!(
+                    "Count of {} ascents by grade and year (converted to {})",
+                    display_scale.discipline().label(),
+                    display_scale.label(),
+                ),
+                Some(display_scale.name()),
+                "grade",
+                &years,
+                grade_year_counts(period_grades),
+            ));
+        }
+    }
+
+    for scale in db.scales(filter)? {
+        let grade_scale = grades::find_scale(&scale).expect("Logged scale should be known");
+
+        if display_scale
+            .is_some_and(|display_scale| display_scale.discipline() == grade_scale.discipline())
+        {
+            continue;
+        }
+
+        tables.push(CrosstabTable::new(
+            format!(
+                "Count of {} ascents by grade and year ({})",
+                grade_scale.discipline().label(),
+                grade_scale.label(),
+            ),
+            Some(grade_scale.name()),
+            "grade",
+            &years,
+            grade_year_counts(db.period_grades(filter, &scale, Period::Year)?),
+        ));
+    }
+
+    Ok(tables)
+}
+
+pub fn render_report(report: &Report, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Text => render_text(report),
+        ReportFormat::Json => render_json(report),
+        ReportFormat::Csv => render_csv(report),
+        ReportFormat::Markdown => render_markdown(report),
+    }
+}
+
+pub fn render_crosstabs(tables: &[CrosstabTable], format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Text => {
+            let tables: Vec<String> = tables
+                .iter()
+                .map(|table| format!("{}:\n{}\n", table.title, table.draw()))
+                .collect();
+
+            tables.join("\n")
+        }
+        ReportFormat::Json => {
+            serde_json::to_string_pretty(tables).expect("Crosstabs should serialize to JSON") + "\n"
+        }
+        ReportFormat::Csv => render_crosstabs_csv(tables),
+        ReportFormat::Markdown => {
+            let tables: Vec<String> = tables
+                .iter()
+                .map(|table| {
+                    let mut cells = table.cells();
+                    let header = cells
+                        .remove(0)
+                        .iter()
+                        .map(|cell| capitalize(cell))
+                        .collect();
+
+                    format!(
+                        "## {}\n\n{}\n",
+                        table.title,
+                        make_markdown_rows(header, cells)
+                    )
+                })
+                .collect();
+
+            tables.join("\n")
+        }
+    }
+}
+
+// One CSV table for every crosstab, which works since they all have the same
+// years, with a leading scale column for tables of grades. Totals are marked
+// as such in a column of their own, so they can't be mistaken for a crag or
+// grade that happens to be called Total.
+fn render_crosstabs_csv(tables: &[CrosstabTable]) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    for (i, table) in tables.iter().enumerate() {
+        let cells = table.cells();
+        let totals = cells.len() - 1;
+
+        for (j, mut line) in cells.into_iter().enumerate() {
+            // Only the first table needs a header
+            if i > 0 && j == 0 {
+                continue;
+            }
+
+            let kind = match j {
+                0 => "kind",
+                j if j == totals => {
+                    line[0].clear();
+                    "total"
+                }
+                _ => "count",
+            };
+
+            if let Some(scale) = table.scale {
+                line.insert(0, if j == 0 { "scale" } else { scale }.to_string());
+            }
+
+            line.insert(0, kind.to_string());
+
+            writer
+                .write_record(line)
+                .expect("Should be able to write CSV to memory");
+        }
+    }
+
+    let csv = writer
+        .into_inner()
+        .expect("Should be able to write CSV to memory");
+
+    String::from_utf8(csv).expect("CSV of strings should be valid UTF-8")
+}
+
+fn make_counts_table(counts: &[Count]) -> String {
     let mut table: Vec<String> = Vec::new();
 
     for count in counts {
@@ -13,24 +1033,707 @@ fn make_counts_table(counts: Vec<Count>) -> String {
     table.join("\n")
 }
 
-pub fn analyze_ascent_db(database: &String) -> Result<String> {
-    let db = AscentDB::new(database)?;
-
-    let total_count = db.total_count()?;
-    let year_counts = db.year_counts()?;
-    let crag_counts = db.crag_counts()?;
-    let grade_counts = db.grade_counts()?;
-
-    let analysis = format!(
-        "Analysis of ascents in {database}\n\n\
-        Total count: {total_count}\n\n\
+fn render_text(report: &Report) -> String {
+    let mut analysis = format!(
+        "Analysis of ascents in {}\n\n\
+        Total count: {}\n\
+        Unique route count: {}\n\n\
         Count of ascents by year:\n{}\n\n\
         Count of ascents by crag:\n{}\n\n\
-        Count of ascents by grade:\n{}",
-        make_counts_table(year_counts),
-        make_counts_table(crag_counts),
-        make_counts_table(grade_counts),
+        Count of ascents by style:\n{}",
+        report.database,
+        report.total_count,
+        report.unique_route_count,
+        make_counts_table(&report.year_counts),
+        make_counts_table(&report.crag_counts),
+        make_counts_table(&report.style_counts),
     );
 
-    Ok(analysis)
+    for (period, counts) in [
+        ("month", &report.month_counts),
+        ("weekday", &report.weekday_counts),
+    ] {
+        analysis.push_str(&format!(
+            "\n\nCount of ascents by {period}:\n{}\n\
+            Activity by {period}: {}",
+            make_counts_table(counts),
+            heat_strip(counts),
+        ));
+    }
+
+    if let Some(calendar) = &report.calendar {
+        analysis.push_str(&format!("\n\n{}:\n{}", calendar.title(), calendar.draw()));
+    }
+
+    for grade_counts in &report.grade_counts {
+        analysis.push_str(&format!(
+            "\n\n{}:\n{}",
+            grade_counts.title(),
+            make_counts_table(&grade_counts.counts),
+        ));
+    }
+
+    for pyramid in &report.pyramids {
+        analysis.push_str(&format!("\n\n{}:\n{}", pyramid.title(), pyramid.draw()));
+    }
+
+    for progression in &report.progressions {
+        analysis.push_str(&format!(
+            "\n\n{}:\n{}",
+            progression.title(),
+            progression.draw(),
+        ));
+    }
+
+    let hardest_ascents = |by_style: bool| -> String {
+        let lines: Vec<String> = report
+            .hardest_ascents
+            .iter()
+            .filter(|hardest| hardest.style.is_some() == by_style)
+            .map(|hardest| format!("{}: {}", hardest.scales(), hardest.description()))
+            .collect();
+
+        lines.join("\n")
+    };
+
+    if !report.hardest_ascents.is_empty() {
+        analysis.push_str(&format!(
+            "\n\nHardest ascents:\n{}\n\n\
+            Hardest ascents by style:\n{}",
+            hardest_ascents(false),
+            hardest_ascents(true),
+        ));
+    }
+
+    analysis + "\n"
+}
+
+fn render_json(report: &Report) -> String {
+    serde_json::to_string_pretty(report).expect("Report should serialize to JSON") + "\n"
+}
+
+// Every count and hardest ascent as one row of a section, so that the whole
+// report fits in a single table
+fn render_csv(report: &Report) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut write = |section: &str, scale: &str, category: &str, value: &str| {
+        writer
+            .write_record([section, scale, category, value])
+            .expect("Should be able to write CSV to memory");
+    };
+
+    write("section", "scale", "category", "value");
+    write("total", "", "", &report.total_count.to_string());
+    write(
+        "unique_routes",
+        "",
+        "",
+        &report.unique_route_count.to_string(),
+    );
+
+    for (section, counts) in [
+        ("year", &report.year_counts),
+        ("crag", &report.crag_counts),
+        ("style", &report.style_counts),
+        ("month", &report.month_counts),
+        ("weekday", &report.weekday_counts),
+    ] {
+        for count in counts {
+            write(section, "", count.category(), &count.value().to_string());
+        }
+    }
+
+    if let Some(calendar) = &report.calendar {
+        for count in &calendar.day_counts {
+            write("day", "", count.category(), &count.value().to_string());
+        }
+    }
+
+    for grade_counts in &report.grade_counts {
+        for count in &grade_counts.counts {
+            write(
+                "grade",
+                grade_counts.scale,
+                count.category(),
+                &count.value().to_string(),
+            );
+        }
+    }
+
+    for pyramid in &report.pyramids {
+        for level in pyramid.levels.iter().filter(|level| level.thin) {
+            write(
+                "thin",
+                pyramid.scale,
+                &level.grade,
+                &level.count.to_string(),
+            );
+        }
+    }
+
+    for progression in &report.progressions {
+        for period in &progression.periods {
+            let rows = [
+                ("count", period.count.to_string()),
+                ("hardest", period.hardest.clone()),
+                ("median", period.median.clone()),
+            ];
+
+            for (value, cell) in rows {
+                write(
+                    &format!("{value}_by_{}", progression.period),
+                    progression.scale,
+                    &period.period,
+                    &cell,
+                );
+            }
+        }
+    }
+
+    for hardest in &report.hardest_ascents {
+        let style = hardest.style.map_or("all", |style| style.name());
+        write("hardest", hardest.scale, style, &hardest.description());
+    }
+
+    let csv = writer
+        .into_inner()
+        .expect("Should be able to write CSV to memory");
+
+    String::from_utf8(csv).expect("CSV of strings should be valid UTF-8")
+}
+
+fn make_markdown_table<const N: usize>(headers: [&str; N], rows: Vec<[String; N]>) -> String {
+    make_markdown_rows(
+        headers.map(String::from).into(),
+        rows.into_iter().map(Vec::from).collect(),
+    )
+}
+
+fn make_markdown_rows(headers: Vec<String>, rows: Vec<Vec<String>>) -> String {
+    let make_line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+
+    let mut table = vec![
+        make_line(headers.clone()),
+        make_line(vec!["---".to_string(); headers.len()]),
+    ];
+
+    for row in rows {
+        table.push(make_line(
+            row.into_iter()
+                .map(|cell| cell.replace('|', "\\|"))
+                .collect(),
+        ));
+    }
+
+    table.join("\n")
+}
+
+fn make_markdown_counts_table(header: &str, counts: &[Count]) -> String {
+    let rows = counts
+        .iter()
+        .map(|count| [count.category().clone(), count.value().to_string()])
+        .collect();
+
+    make_markdown_table([header, "Count"], rows)
+}
+
+fn render_markdown(report: &Report) -> String {
+    let mut sections = vec![
+        format!("# Analysis of ascents in {}", report.database),
+        format!(
+            "- Total count: {}\n- Unique route count: {}",
+            report.total_count, report.unique_route_count,
+        ),
+        "## Count of ascents by year".to_string(),
+        make_markdown_counts_table("Year", &report.year_counts),
+        "## Count of ascents by crag".to_string(),
+        make_markdown_counts_table("Crag", &report.crag_counts),
+        "## Count of ascents by style".to_string(),
+        make_markdown_counts_table("Style", &report.style_counts),
+    ];
+
+    for (period, counts) in [
+        ("Month", &report.month_counts),
+        ("Weekday", &report.weekday_counts),
+    ] {
+        sections.push(format!("## Count of ascents by {}", period.to_lowercase()));
+        sections.push(make_markdown_counts_table(period, counts));
+        sections.push(format!(
+            "Activity by {}: {}",
+            period.to_lowercase(),
+            heat_strip(counts)
+        ));
+    }
+
+    if let Some(calendar) = &report.calendar {
+        sections.push(format!("## {}", calendar.title()));
+        sections.push(format!("```\n{}\n```", calendar.draw()));
+    }
+
+    for grade_counts in &report.grade_counts {
+        sections.push(format!("## {}", grade_counts.title()));
+        sections.push(make_markdown_counts_table("Grade", &grade_counts.counts));
+    }
+
+    for pyramid in &report.pyramids {
+        sections.push(format!("## {}", pyramid.title()));
+        sections.push(format!("```\n{}\n```", pyramid.draw()));
+    }
+
+    for progression in &report.progressions {
+        let headers = progression.headers();
+
+        sections.push(format!("## {}", progression.title()));
+        sections.push(make_markdown_table(
+            headers.each_ref().map(String::as_str),
+            progression.rows(),
+        ));
+        sections.push(format!(
+            "Hardest grade by {}: {}",
+            progression.period,
+            progression.sparkline(),
+        ));
+    }
+
+    if !report.hardest_ascents.is_empty() {
+        let rows = report
+            .hardest_ascents
+            .iter()
+            .map(|hardest| [hardest.scales(), hardest.description()])
+            .collect();
+
+        sections.push("## Hardest ascents".to_string());
+        sections.push(make_markdown_table(["Scales", "Ascent"], rows));
+    }
+
+    sections.join("\n\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Route;
+    use time::macros::date;
+
+    fn report() -> Report {
+        let count = |category: &str, value| Count::new(category.to_string(), value);
+
+        let ascent = Ascent::new(
+            Route::new(
+                "Slither".to_string(),
+                "5.7".to_string(),
+                "yds".to_string(),
+                "Reimers Ranch".to_string(),
+            )
+            .unwrap(),
+            date!(2022 - 06 - 27),
+            Style::Onsight,
+        );
+
+        Report {
+            database: "ascent.db".to_string(),
+            total_count: 2,
+            unique_route_count: 1,
+            year_counts: vec![count("2022", 1), count("2023", 1)],
+            crag_counts: vec![count("Reimers Ranch", 2)],
+            style_counts: vec![count("onsight", 1), count("redpoint", 1)],
+            month_counts: vec![count("June", 2), count("July", 0)],
+            weekday_counts: vec![count("Monday", 1), count("Tuesday", 1)],
+            calendar: None,
+            grade_counts: vec![GradeCounts {
+                discipline: "route",
+                scale: "french",
+                converted: true,
+                counts: vec![count("5a", 2)],
+            }],
+            pyramids: vec![Pyramid {
+                discipline: "route",
+                scale: "french",
+                converted: true,
+                levels: [("5b", 1, false), ("5a+", 0, true), ("5a", 1, false)]
+                    .map(|(grade, count, thin)| PyramidLevel {
+                        grade: grade.to_string(),
+                        count,
+                        thin,
+                    })
+                    .into(),
+            }],
+            progressions: vec![Progression {
+                discipline: "route",
+                scale: "french",
+                converted: true,
+                period: "year",
+                periods: vec![ProgressionPeriod {
+                    period: "2022".to_string(),
+                    count: 2,
+                    hardest: "5a".to_string(),
+                    median: "5a".to_string(),
+                    hardest_order: 9,
+                }],
+            }],
+            hardest_ascents: vec![
+                HardestAscent {
+                    discipline: "route",
+                    scale: "french",
+                    converted: true,
+                    style: None,
+                    grade: "5a".to_string(),
+                    ascent: ascent.clone(),
+                },
+                HardestAscent {
+                    discipline: "route",
+                    scale: "french",
+                    converted: true,
+                    style: Some(Style::Onsight),
+                    grade: "5a".to_string(),
+                    ascent,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn text_report() {
+        let expected = "\
+Analysis of ascents in ascent.db
+
+Total count: 2
+Unique route count: 1
+
+Count of ascents by year:
+   1  2022
+   1  2023
+
+Count of ascents by crag:
+   2  Reimers Ranch
+
+Count of ascents by style:
+   1  onsight
+   1  redpoint
+
+Count of ascents by month:
+   2  June
+   0  July
+Activity by month: #.
+
+Count of ascents by weekday:
+   1  Monday
+   1  Tuesday
+Activity by weekday: ##
+
+Count of route ascents by grade (converted to French):
+   2  5a
+
+Grade pyramid of route ascents (converted to French):
+ 5b  #  1
+5a+     0  thin
+ 5a  #  1
+
+Progression of route ascents by year (converted to French):
+Year  Count  Hardest  Median
+2022      2  5a       5a
+Hardest grade by year: ▁
+
+Hardest ascents:
+All route scales: Slither 5.7 (YDS) at Reimers Ranch on 2022-06-27 (onsight), converted to 5a
+
+Hardest ascents by style:
+All route scales onsight: Slither 5.7 (YDS) at Reimers Ranch on 2022-06-27 (onsight), converted to 5a
+";
+
+        assert_eq!(render_report(&report(), ReportFormat::Text), expected);
+    }
+
+    #[test]
+    fn pyramid() {
+        let grade_counts = GradeCounts {
+            discipline: "route",
+            scale: "french",
+            converted: false,
+            counts: Vec::new(),
+        };
+        let counts = || -> Vec<Count> {
+            [("6a", 6), ("6a+", 2), ("6b", 1), ("6b+", 0), ("6c", 1)]
+                .map(|(grade, count)| Count::new(grade.to_string(), count))
+                .into()
+        };
+
+        // Grades without any leads are thin, unlike the grade above them
+        let expected = [
+            " 6c       #       1",
+            "6b+               0  thin",
+            " 6b       #       1",
+            "6a+      ###      2  thin",
+            " 6a  ###########  6",
+        ]
+        .join("\n");
+
+        assert_eq!(Pyramid::new(&grade_counts, counts(), None).draw(), expected);
+
+        let pyramid = Pyramid::new(&grade_counts, counts(), Some(2));
+        let grades: Vec<&String> = pyramid.levels.iter().map(|level| &level.grade).collect();
+
+        assert_eq!(grades, ["6c", "6b+"]);
+        assert!(pyramid.levels[1].thin);
+        assert_eq!(pyramid.title(), "Grade pyramid of route ascents (French)");
+
+        // Bars are scaled down to fit, while staying centered
+        let counts = vec![
+            Count::new("6a".to_string(), 100),
+            Count::new("7a".to_string(), 1),
+        ];
+
+        let expected = format!("7a  {:^41}  1  thin\n6a  {}  100", "#", "#".repeat(41));
+        assert_eq!(Pyramid::new(&grade_counts, counts, None).draw(), expected);
+    }
+
+    #[test]
+    fn progression() {
+        let grade_counts = GradeCounts {
+            discipline: "route",
+            scale: "yds",
+            converted: false,
+            counts: Vec::new(),
+        };
+
+        let period_grades = [
+            ("2021", "5.9", 10),
+            ("2022", "5.7", 5),
+            ("2022", "5.10a", 12),
+            ("2022", "5.10b", 14),
+            ("2022", "5.11a", 20),
+            ("2023", "5.12a", 28),
+        ]
+        .map(|(period, grade, order)| (period.to_string(), grade.to_string(), order))
+        .into();
+
+        let progression = Progression::new(&grade_counts, Period::Year, period_grades);
+
+        let expected = "\
+Year  Count  Hardest  Median
+2021      1  5.9      5.9
+2022      4  5.11a    5.10a
+2023      1  5.12a    5.12a
+Hardest grade by year: ▁▅█";
+
+        assert_eq!(progression.draw(), expected);
+
+        // Small steps up round to the nearest bar instead of being lost
+        let period_grades = [
+            ("2021", "5.9", 28),
+            ("2022", "5.10a", 30),
+            ("2023", "5.12a", 54),
+        ]
+        .map(|(period, grade, order)| (period.to_string(), grade.to_string(), order))
+        .into();
+        let steps = Progression::new(&grade_counts, Period::Year, period_grades);
+        assert_eq!(steps.sparkline(), "▁▂█");
+        assert_eq!(
+            progression.title(),
+            "Progression of route ascents by year (YDS)",
+        );
+    }
+
+    #[test]
+    fn calendar() {
+        let calendar = Calendar {
+            year: 2023,
+            day_counts: [
+                ("2023-01-01", 1),
+                ("2023-01-04", 2),
+                ("2023-01-05", 4),
+                ("2023-03-01", 3),
+            ]
+            .map(|(date, count)| Count::new(date.to_string(), count))
+            .into(),
+        };
+
+        // 2023 starts on a Sunday, so the first week only has one day
+        let lines: Vec<String> = calendar.draw().lines().map(String::from).collect();
+
+        assert_eq!(lines.len(), 9);
+        assert!(lines[0].starts_with("    Jan  Feb Mar"));
+        assert!(lines[1].starts_with("Mon  ......."));
+        assert!(lines[3].starts_with("Wed  +.......*.."));
+        assert!(lines[4].starts_with("Thu  #.."));
+        assert!(lines[7].starts_with("Sun :....."));
+        assert_eq!(lines[8], "Less .:+*# More, up to 4 ascent(s) in a day");
+
+        // Years before 1000 are padded like their dates
+        let calendar = Calendar {
+            year: 999,
+            day_counts: vec![Count::new("0999-01-01".to_string(), 1)],
+        };
+        assert!(calendar.draw().lines().any(|line| line.contains('#')));
+
+        let filter = AscentFilter {
+            year: Some(2022),
+            ..Default::default()
+        };
+        assert_eq!(calendar_filter(&filter, 2022).unwrap().year, Some(2022));
+        assert_eq!(
+            calendar_filter(&filter, 2023).unwrap_err(),
+            Error::User(User::CalendarYearMismatch),
+        );
+        assert_eq!(
+            calendar_filter(&AscentFilter::default(), 12345).unwrap_err(),
+            Error::User(User::InvalidYear),
+        );
+
+        assert_eq!(
+            every_weekday(vec![Count::new("0".to_string(), 3)])
+                .iter()
+                .map(|count| (count.category().as_str(), count.value()))
+                .collect::<Vec<_>>(),
+            [
+                ("Monday", 0),
+                ("Tuesday", 0),
+                ("Wednesday", 0),
+                ("Thursday", 0),
+                ("Friday", 0),
+                ("Saturday", 0),
+                ("Sunday", 3),
+            ],
+        );
+
+        let months = every_month(vec![Count::new("12".to_string(), 1)]);
+        assert_eq!(months.len(), 12);
+        assert_eq!(months[0].category(), "January");
+        assert_eq!(months[11].value(), 1);
+        assert_eq!(heat_strip(&months), "...........#");
+    }
+
+    #[test]
+    fn crosstab() {
+        let years = ["2021", "2022", "2023"].map(String::from);
+        let counts = [
+            ("Reimers Ranch", "2021", 3),
+            ("Reimers Ranch", "2023", 1),
+            ("Enchanted Rock", "2022", 10),
+        ]
+        .map(|(crag, year, count)| (crag.to_string(), year.to_string(), count))
+        .into();
+
+        let table = CrosstabTable::new("Crags".to_string(), None, "crag", &years, counts);
+
+        let expected = "\
+Crag            2021  2022  2023  Total
+Reimers Ranch      3     0     1      4
+Enchanted Rock     0    10     0     10
+Total              3    10     1     14";
+
+        assert_eq!(table.draw(), expected);
+
+        let grades = CrosstabTable::new(
+            "Grades".to_string(),
+            Some("yds"),
+            "grade",
+            &years,
+            vec![("5.9".to_string(), "2022".to_string(), 1)],
+        );
+
+        let expected = "\
+kind,crag,2021,2022,2023,total
+count,Reimers Ranch,3,0,1,4
+count,Enchanted Rock,0,10,0,10
+total,,3,10,1,14
+";
+        assert_eq!(render_crosstabs(&[table], ReportFormat::Csv), expected);
+
+        let expected = "\
+kind,scale,grade,2021,2022,2023,total
+count,yds,5.9,0,1,0,1
+total,yds,,0,1,0,1
+";
+        assert_eq!(render_crosstabs(&[grades], ReportFormat::Csv), expected);
+
+        // A crag called Total is still told apart from the totals
+        let table = CrosstabTable::new(
+            "Crags".to_string(),
+            None,
+            "crag",
+            &years,
+            vec![("Total".to_string(), "2021".to_string(), 2)],
+        );
+
+        let expected = "\
+kind,crag,2021,2022,2023,total
+count,Total,2,0,0,2
+total,,2,0,0,2
+";
+        assert_eq!(render_crosstabs(&[table], ReportFormat::Csv), expected);
+    }
+
+    #[test]
+    fn csv_report() {
+        let expected = "\
+section,scale,category,value
+total,,,2
+unique_routes,,,1
+year,,2022,1
+year,,2023,1
+crag,,Reimers Ranch,2
+style,,onsight,1
+style,,redpoint,1
+month,,June,2
+month,,July,0
+weekday,,Monday,1
+weekday,,Tuesday,1
+grade,french,5a,2
+thin,french,5a+,0
+count_by_year,french,2022,2
+hardest_by_year,french,2022,5a
+median_by_year,french,2022,5a
+hardest,french,all,\"Slither 5.7 (YDS) at Reimers Ranch on 2022-06-27 (onsight), converted to 5a\"
+hardest,french,onsight,\"Slither 5.7 (YDS) at Reimers Ranch on 2022-06-27 (onsight), converted to 5a\"
+";
+
+        assert_eq!(render_report(&report(), ReportFormat::Csv), expected);
+    }
+
+    #[test]
+    fn json_report() {
+        let json: serde_json::Value =
+            serde_json::from_str(&render_report(&report(), ReportFormat::Json)).unwrap();
+
+        assert_eq!(json["total_count"], 2);
+        assert_eq!(json["year_counts"][1]["category"], "2023");
+        assert_eq!(json["grade_counts"][0]["scale"], "french");
+        assert_eq!(json["hardest_ascents"][1]["style"], "onsight");
+        assert_eq!(
+            json["hardest_ascents"][1]["ascent"]["route"]["name"],
+            "Slither"
+        );
+    }
+
+    #[test]
+    fn markdown_report() {
+        let markdown = render_report(&report(), ReportFormat::Markdown);
+
+        assert!(markdown.starts_with("# Analysis of ascents in ascent.db\n\n- Total count: 2\n"));
+        assert!(markdown.contains(
+            "## Count of ascents by crag\n\n| Crag | Count |\n| --- | --- |\n| Reimers Ranch | 2 |\n"
+        ));
+        assert!(markdown.contains(
+            "## Grade pyramid of route ascents (converted to French)\n\n```\n 5b  #  1\n5a+     0  thin\n"
+        ));
+        assert!(markdown.contains(
+            "| Year | Count | Hardest | Median |\n| --- | --- | --- | --- |\n| 2022 | 2 | 5a | 5a |\n\nHardest grade by year: ▁\n"
+        ));
+        assert!(markdown.contains("| All route scales onsight | Slither 5.7 (YDS)"));
+    }
+
+    #[test]
+    fn hardest_ascent_description() {
+        let mut hardest = report().hardest_ascents.remove(0);
+        assert!(hardest.description().ends_with(", converted to 5a"));
+
+        // Ascents already in the scale of the report aren't converted
+        hardest.scale = "yds";
+        hardest.grade = "5.7".to_string();
+        assert_eq!(
+            hardest.description(),
+            "Slither 5.7 (YDS) at Reimers Ranch on 2022-06-27 (onsight)",
+        );
+    }
 }
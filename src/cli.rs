@@ -1,28 +1,525 @@
 use crate::{
-    analyze,
+    analyze::{self, AnalyzeOptions},
     error::{Error, Result, User},
-    init,
-    models::{Ascent, AscentDB, Route},
+    export, grades,
+    import::{self, ImportOptions},
+    init, list, migrate,
+    models::{
+        Ascent, AscentDB, AscentFilter, Crosstab, Format, Period, ReportFormat, Route, Sort, Style,
+    },
     utils,
 };
-use time::Date;
+use std::collections::{HashMap, HashSet};
 
-pub const USAGE: &str = "Usage: ascents [-h] {init,log,drop,analyze} database";
+pub const USAGE: &str = "\
+Usage: ascents <subcommand> database [options]
+Run 'ascents help' for a list of subcommands, or 'ascents help <subcommand>' for its options";
 
 enum Subcommand {
     Init,
     Log,
     Drop,
+    Edit,
+    List,
+    Search,
     Analyze,
+    CragRename,
+    CragMerge,
+    Export,
+    Import,
+    Migrate,
 }
 
-pub struct Args {
+struct OptionSpec {
+    long: &'static str,
+    short: Option<char>,
+    // Name of the value the option takes, or None for flags
+    value: Option<&'static str>,
+    help: &'static str,
+}
+
+struct SubcommandSpec {
     subcommand: Subcommand,
-    database: String,
+    name: &'static str,
+    about: &'static str,
+    // Names of the required positional args, in order
+    args: &'static [&'static str],
+    options: &'static [OptionSpec],
+}
+
+const HELP: OptionSpec = OptionSpec {
+    long: "help",
+    short: Some('h'),
+    value: None,
+    help: "Show this help",
+};
+
+const VERSION: OptionSpec = OptionSpec {
+    long: "version",
+    short: Some('V'),
+    value: None,
+    help: "Show the version",
+};
+
+// Options of subcommands that pick out a logged ascent, such as from the
+// results of search, with any not given being prompted for
+const FIND_ASCENT_OPTIONS: &[OptionSpec] = &[
+    OptionSpec {
+        long: "route",
+        short: None,
+        value: Some("NAME"),
+        help: "Name of the route",
+    },
+    OptionSpec {
+        long: "grade",
+        short: None,
+        value: Some("GRADE"),
+        help: "Grade of the route",
+    },
+    OptionSpec {
+        long: "scale",
+        short: None,
+        value: Some("SCALE"),
+        help: "Grade scale of the route",
+    },
+    OptionSpec {
+        long: "crag",
+        short: None,
+        value: Some("CRAG"),
+        help: "Crag where the route is located",
+    },
+    OptionSpec {
+        long: "date",
+        short: None,
+        value: Some("YYYY-MM-DD"),
+        help: "Date of the ascent, for routes sent more than once",
+    },
+];
+
+static SUBCOMMANDS: [SubcommandSpec; 12] = [
+    SubcommandSpec {
+        subcommand: Subcommand::Init,
+        name: "init",
+        about: "Initialize a new ascent database",
+        args: &["database"],
+        options: &[],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::Log,
+        name: "log",
+        about: "Log an ascent, prompting for any fields not given as options",
+        args: &["database"],
+        options: &[
+            OptionSpec {
+                long: "route",
+                short: None,
+                value: Some("NAME"),
+                help: "Name of the route",
+            },
+            OptionSpec {
+                long: "grade",
+                short: None,
+                value: Some("GRADE"),
+                help: "Grade of the route",
+            },
+            OptionSpec {
+                long: "scale",
+                short: None,
+                value: Some("SCALE"),
+                help: "Grade scale of the route [default: yds]",
+            },
+            OptionSpec {
+                long: "crag",
+                short: None,
+                value: Some("CRAG"),
+                help: "Crag where the route is located",
+            },
+            OptionSpec {
+                long: "date",
+                short: None,
+                value: Some("YYYY-MM-DD"),
+                help: "Date of the ascent",
+            },
+            OptionSpec {
+                long: "style",
+                short: None,
+                value: Some("STYLE"),
+                help: "Style of the ascent [default: redpoint]",
+            },
+            OptionSpec {
+                long: "notes",
+                short: None,
+                value: Some("TEXT"),
+                help: "Any notes on the ascent",
+            },
+            OptionSpec {
+                long: "yes",
+                short: Some('y'),
+                value: None,
+                help: "Skip confirmations, using the defaults for fields not given",
+            },
+        ],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::Drop,
+        name: "drop",
+        about: "Drop a logged ascent",
+        args: &["database"],
+        options: FIND_ASCENT_OPTIONS,
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::Edit,
+        name: "edit",
+        about: "Edit a logged ascent, prompting for the new value of each field",
+        args: &["database"],
+        options: FIND_ASCENT_OPTIONS,
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::List,
+        name: "list",
+        about: "List logged ascents, optionally filtered and sorted",
+        args: &["database"],
+        options: &[
+            OptionSpec {
+                long: "crag",
+                short: None,
+                value: Some("CRAG"),
+                help: "Only list ascents at CRAG",
+            },
+            OptionSpec {
+                long: "route",
+                short: None,
+                value: Some("TEXT"),
+                help: "Only list ascents of routes whose name contains TEXT",
+            },
+            OptionSpec {
+                long: "grade-min",
+                short: None,
+                value: Some("GRADE"),
+                help: "Only list ascents graded GRADE or harder",
+            },
+            OptionSpec {
+                long: "grade-max",
+                short: None,
+                value: Some("GRADE"),
+                help: "Only list ascents graded GRADE or easier",
+            },
+            OptionSpec {
+                long: "scale",
+                short: None,
+                value: Some("SCALE"),
+                help: "Grade scale of --grade-min and --grade-max [default: yds]",
+            },
+            OptionSpec {
+                long: "since",
+                short: None,
+                value: Some("YYYY-MM-DD"),
+                help: "Only list ascents on or after this date",
+            },
+            OptionSpec {
+                long: "until",
+                short: None,
+                value: Some("YYYY-MM-DD"),
+                help: "Only list ascents on or before this date",
+            },
+            OptionSpec {
+                long: "year",
+                short: None,
+                value: Some("YEAR"),
+                help: "Only list ascents in YEAR",
+            },
+            OptionSpec {
+                long: "sort",
+                short: None,
+                value: Some("SORT"),
+                help: "Sort by date, grade or crag [default: date]",
+            },
+            OptionSpec {
+                long: "reverse",
+                short: Some('r'),
+                value: None,
+                help: "Reverse the sort order",
+            },
+        ],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::Search,
+        name: "search",
+        about: "Search the route, crag and notes of logged ascents, best matches first",
+        args: &["database", "text"],
+        options: &[],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::Analyze,
+        name: "analyze",
+        about: "Analyze logged ascents",
+        args: &["database"],
+        options: &[
+            OptionSpec {
+                long: "scale",
+                short: None,
+                value: Some("SCALE"),
+                help: "Convert grades of the same discipline into SCALE, which is also the \
+                    scale of --grade-min and --grade-max [default: yds]",
+            },
+            OptionSpec {
+                long: "crag",
+                short: None,
+                value: Some("CRAG"),
+                help: "Only analyze ascents at CRAG",
+            },
+            OptionSpec {
+                long: "grade-min",
+                short: None,
+                value: Some("GRADE"),
+                help: "Only analyze ascents graded GRADE or harder",
+            },
+            OptionSpec {
+                long: "grade-max",
+                short: None,
+                value: Some("GRADE"),
+                help: "Only analyze ascents graded GRADE or easier",
+            },
+            OptionSpec {
+                long: "since",
+                short: None,
+                value: Some("YYYY-MM-DD"),
+                help: "Only analyze ascents on or after this date",
+            },
+            OptionSpec {
+                long: "until",
+                short: None,
+                value: Some("YYYY-MM-DD"),
+                help: "Only analyze ascents on or before this date",
+            },
+            OptionSpec {
+                long: "year",
+                short: None,
+                value: Some("YEAR"),
+                help: "Only analyze ascents in YEAR",
+            },
+            OptionSpec {
+                long: "pyramid-grades",
+                short: None,
+                value: Some("N"),
+                help: "Only show the hardest N grades of each grade pyramid",
+            },
+            OptionSpec {
+                long: "progression",
+                short: None,
+                value: Some("PERIOD"),
+                help: "Show the progression of grades by year or month [default: year]",
+            },
+            OptionSpec {
+                long: "calendar",
+                short: None,
+                value: Some("YEAR"),
+                help: "Show a calendar of ascents on each day of YEAR",
+            },
+            OptionSpec {
+                long: "crosstab",
+                short: None,
+                value: Some("ROWS"),
+                help: "Only show a table of ascents by year, with rows of each grade or crag",
+            },
+            OptionSpec {
+                long: "format",
+                short: None,
+                value: Some("FORMAT"),
+                help: "Format of the report, one of text, json, csv or markdown [default: text]",
+            },
+        ],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::CragRename,
+        name: "crag rename",
+        about: "Rename a crag",
+        args: &["database", "old", "new"],
+        options: &[OptionSpec {
+            long: "yes",
+            short: Some('y'),
+            value: None,
+            help: "Skip confirmation",
+        }],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::CragMerge,
+        name: "crag merge",
+        about: "Merge a crag into another, such as a misspelling of it",
+        args: &["database", "from", "into"],
+        options: &[OptionSpec {
+            long: "yes",
+            short: Some('y'),
+            value: None,
+            help: "Skip confirmation",
+        }],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::Export,
+        name: "export",
+        about: "Print every logged ascent in a file format",
+        args: &["database"],
+        options: &[OptionSpec {
+            long: "format",
+            short: None,
+            value: Some("FORMAT"),
+            help: "Format to print in, one of csv, json or jsonl [default: csv]",
+        }],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::Import,
+        name: "import",
+        about: "Log every ascent in a file, skipping any already logged",
+        args: &["database", "file"],
+        options: &[
+            OptionSpec {
+                long: "format",
+                short: None,
+                value: Some("FORMAT"),
+                help: "Format of the file, one of csv, json, jsonl, mountainproject, thecrag \
+                    or 8a [default: csv]",
+            },
+            OptionSpec {
+                long: "leads-only",
+                short: None,
+                value: None,
+                help: "Leave out ascents that weren't led, such as topropes and follows",
+            },
+            OptionSpec {
+                long: "crag-level",
+                short: None,
+                value: Some("N"),
+                help: "Use part N of Mountain Project locations as the crag, counting from 1 \
+                    at the top [default: the second to last part]",
+            },
+            OptionSpec {
+                long: "dry-run",
+                short: None,
+                value: None,
+                help: "Show the ascents that would be imported without logging them",
+            },
+        ],
+    },
+    SubcommandSpec {
+        subcommand: Subcommand::Migrate,
+        name: "migrate",
+        about: "Migrate an ascent database to the latest schema version",
+        args: &["database"],
+        options: &[],
+    },
+];
+
+// Finds a subcommand by name, reading the second word of the name from the
+// args for subcommands grouped under a first one, such as crag rename
+fn find_subcommand(
+    name: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<&'static SubcommandSpec> {
+    let group = format!("{name} ");
+    let mut name = name.to_string();
+
+    if SUBCOMMANDS.iter().any(|spec| spec.name.starts_with(&group)) {
+        match args.next() {
+            Some(second) => name.push_str(&format!(" {second}")),
+            None => return Err(Error::User(User::MissingArg("subcommand"))),
+        }
+    }
+
+    SUBCOMMANDS
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or(Error::User(User::InvalidSubcommand(name)))
+}
+
+// Lines up the help text of each (name, help) pair in a column
+fn help_table(rows: &[(String, &str)]) -> String {
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    rows.iter()
+        .map(|(name, help)| format!("  {name:<width$}  {help}"))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn option_help_row(option: &OptionSpec) -> (String, &'static str) {
+    let short = match option.short {
+        Some(short) => format!("-{short}, "),
+        None => "    ".to_string(),
+    };
+    let value = match option.value {
+        Some(value) => format!(" {value}"),
+        None => String::new(),
+    };
+
+    (format!("{short}--{}{value}", option.long), option.help)
+}
+
+fn help() -> String {
+    let mut subcommands: Vec<(String, &str)> = SUBCOMMANDS
+        .iter()
+        .map(|spec| (spec.name.to_string(), spec.about))
+        .collect();
+    subcommands.push(("help".to_string(), "Show help for a subcommand"));
+
+    format!(
+        "Command line app for logging rock climbing ascents\n\n\
+        Usage: ascents <subcommand> database [options]\n\n\
+        Subcommands:\n{}\n\n\
+        Options:\n{}\n\n\
+        Run 'ascents help <subcommand>' for the options of a subcommand",
+        help_table(&subcommands),
+        help_table(&[option_help_row(&HELP), option_help_row(&VERSION)]),
+    )
+}
+
+fn subcommand_help(spec: &SubcommandSpec) -> String {
+    let options: Vec<(String, &str)> = spec
+        .options
+        .iter()
+        .chain([&HELP])
+        .map(option_help_row)
+        .collect();
+
+    format!(
+        "{}\n\nUsage: ascents {} {} [options]\n\nOptions:\n{}",
+        spec.about,
+        spec.name,
+        spec.args.join(" "),
+        help_table(&options),
+    )
+}
+
+enum Parsed {
+    Args(Args),
+    Help(String),
+    Version,
+}
+
+pub struct Args {
+    spec: &'static SubcommandSpec,
+    args: Vec<String>,
+    values: HashMap<&'static str, String>,
+    flags: HashSet<&'static str>,
 }
 
 impl Args {
-    pub fn new(mut args: impl Iterator<Item = String>) -> Result<Self> {
+    pub fn new(args: impl Iterator<Item = String>) -> Result<Self> {
+        // Immediate successful exit if the args were actually a request for
+        // help or the version
+        match Self::parse(args)? {
+            Parsed::Args(args) => Ok(args),
+            Parsed::Help(help) => {
+                println!("{help}");
+                std::process::exit(0);
+            }
+            Parsed::Version => {
+                println!("ascents {}", env!("CARGO_PKG_VERSION"));
+                std::process::exit(0);
+            }
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Parsed> {
         args.next();
 
         let subcommand = match args.next() {
@@ -30,79 +527,404 @@ impl Args {
             None => return Err(Error::User(User::MissingArg("subcommand"))),
         };
 
-        let subcommand = match subcommand.as_str() {
-            // Immediate successful exit with usage if first arg
-            // was actually a request for help
-            "-h" | "--help" => {
-                println!("{USAGE}");
-                std::process::exit(0);
+        let spec = match subcommand.as_str() {
+            "-h" | "--help" => return Ok(Parsed::Help(help())),
+            "-V" | "--version" => return Ok(Parsed::Version),
+            "help" => {
+                return match args.next() {
+                    Some(name) => Ok(Parsed::Help(subcommand_help(find_subcommand(
+                        &name, &mut args,
+                    )?))),
+                    None => Ok(Parsed::Help(help())),
+                }
             }
-            "init" => Subcommand::Init,
-            "log" => Subcommand::Log,
-            "drop" => Subcommand::Drop,
-            "analyze" => Subcommand::Analyze,
-            _ => return Err(Error::User(User::InvalidSubcommand)),
+            name => find_subcommand(name, &mut args)?,
         };
 
-        let database = match args.next() {
-            Some(arg) => arg,
-            None => return Err(Error::User(User::MissingArg("database"))),
+        let mut parsed = Self {
+            spec,
+            args: vec![],
+            values: HashMap::new(),
+            flags: HashSet::new(),
         };
+        let mut options_done = false;
+
+        while let Some(arg) = args.next() {
+            if options_done || arg == "-" || !arg.starts_with('-') {
+                if parsed.args.len() == spec.args.len() {
+                    return Err(Error::User(User::UnexpectedArg(arg)));
+                }
+
+                parsed.args.push(arg);
+                continue;
+            }
+
+            if arg == "--" {
+                options_done = true;
+                continue;
+            }
 
-        if args.next().is_some() {
-            return Err(Error::User(User::TooManyArgs));
+            if arg == "-h" || arg == "--help" {
+                return Ok(Parsed::Help(subcommand_help(spec)));
+            }
+
+            // Long options can be given their value inline, as in --crag=Name
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+
+            let option = spec
+                .options
+                .iter()
+                .find(|option| match name.strip_prefix("--") {
+                    Some(long) => option.long == long,
+                    None => option
+                        .short
+                        .is_some_and(|short| name == format!("-{short}")),
+                })
+                .ok_or_else(|| Error::User(User::InvalidOption(name.to_string(), spec.name)))?;
+
+            match (option.value, inline_value) {
+                (None, None) => {
+                    parsed.flags.insert(option.long);
+                }
+                (None, Some(_)) => {
+                    return Err(Error::User(User::InvalidOption(arg, spec.name)));
+                }
+                (Some(_), Some(value)) => {
+                    parsed.values.insert(option.long, value);
+                }
+                (Some(_), None) => match args.next() {
+                    Some(value) => {
+                        parsed.values.insert(option.long, value);
+                    }
+                    None => return Err(Error::User(User::MissingOptionValue(name.to_string()))),
+                },
+            }
         }
 
-        Ok(Self {
-            subcommand,
-            database,
-        })
+        if let Some(missing) = spec.args.get(parsed.args.len()) {
+            return Err(Error::User(User::MissingArg(missing)));
+        }
+
+        Ok(Parsed::Args(parsed))
+    }
+
+    // Value of a required positional arg, which parsing guarantees is present
+    fn arg(&self, name: &str) -> &String {
+        let index = self
+            .spec
+            .args
+            .iter()
+            .position(|arg| *arg == name)
+            .expect("positional arg should be declared by the subcommand");
+
+        &self.args[index]
+    }
+
+    fn database(&self) -> &String {
+        self.arg("database")
+    }
+
+    fn value(&self, option: &str) -> Option<&String> {
+        self.values.get(option)
+    }
+
+    fn flag(&self, option: &str) -> bool {
+        self.flags.contains(option)
     }
 }
 
-fn get_route() -> Result<Route> {
-    let name = utils::input("Enter the name of the route: ");
-    let grade = utils::input("Enter the grade of the route: ");
-    let crag = utils::input("Enter the name of the crag where the route is located: ");
+// Uses the value given on the command line, or prompts for one otherwise
+fn arg_or_input(arg: Option<&String>, prompt: &str) -> String {
+    match arg {
+        Some(value) => value.clone(),
+        None => utils::input(prompt),
+    }
+}
+
+// Prompts for a value, using the default if none is entered
+fn input_with_default(prompt: &str, default: &str) -> String {
+    let value = utils::input(&format!("{prompt} [{default}]: "));
+
+    if value.is_empty() {
+        default.to_string()
+    } else {
+        value
+    }
+}
+
+// Like arg_or_input, but for fields with a default, which is used without
+// prompting when the user has already said yes to everything
+fn arg_or_input_with_default(
+    args: &Args,
+    arg: Option<&String>,
+    prompt: &str,
+    default: &str,
+) -> String {
+    match arg {
+        Some(value) => value.clone(),
+        None if args.flag("yes") => default.to_string(),
+        None => input_with_default(prompt, default),
+    }
+}
+
+fn get_route(args: &Args) -> Result<Route> {
+    let name = arg_or_input(args.value("route"), "Enter the name of the route: ");
+    let scale = arg_or_input_with_default(
+        args,
+        args.value("scale"),
+        &format!(
+            "Enter the grade scale of the route ({})",
+            grades::scale_names().join(", "),
+        ),
+        grades::DEFAULT_SCALE,
+    );
+    let grade = arg_or_input(args.value("grade"), "Enter the grade of the route: ");
+    let crag = arg_or_input(
+        args.value("crag"),
+        "Enter the name of the crag where the route is located: ",
+    );
 
-    Route::new(name, grade, crag)
+    Route::new(name, grade, scale, crag)
 }
 
-fn parse_date(date: String) -> Result<Date> {
-    Date::parse(&date, utils::DATE_FORMAT).map_err(|_| Error::User(User::InvalidDate))
+fn get_ascent(args: &Args, route: Route) -> Result<Ascent> {
+    let date = arg_or_input(
+        args.value("date"),
+        "Enter the date of the ascent in YYYY-MM-DD format: ",
+    );
+    let date = utils::parse_date(date)?;
+
+    let style = arg_or_input_with_default(
+        args,
+        args.value("style"),
+        &format!(
+            "Enter the style of the ascent ({})",
+            Style::names().join(", "),
+        ),
+        Style::DEFAULT.name(),
+    );
+    let style = Style::new(&style)?;
+
+    let notes = match args.value("notes") {
+        Some(notes) => Some(notes.clone()),
+        None if args.flag("yes") => None,
+        None => Some(utils::input(
+            "Enter any notes on the ascent (leave blank for none): ",
+        )),
+    };
+    let notes = notes.filter(|notes| !notes.is_empty());
+
+    Ok(Ascent::new(route, date, style).with_notes(notes))
 }
 
-fn get_ascent() -> Result<Ascent> {
-    let route = get_route()?;
+// Prompts for a new value of each field of an ascent, keeping the current
+// value of any left blank
+fn get_edited_ascent(ascent: &Ascent) -> Result<Ascent> {
+    let route = ascent.route();
+
+    let name = input_with_default("Enter the name of the route", route.name());
+    let scale = input_with_default(
+        &format!(
+            "Enter the grade scale of the route ({})",
+            grades::scale_names().join(", "),
+        ),
+        route.scale(),
+    );
+    let grade = input_with_default("Enter the grade of the route", route.grade());
+    let crag = input_with_default(
+        "Enter the name of the crag where the route is located",
+        route.crag(),
+    );
+    let route = Route::new(name, grade, scale, crag)?;
+
+    let date = input_with_default(
+        "Enter the date of the ascent in YYYY-MM-DD format",
+        &ascent.date().to_string(),
+    );
+    let date = utils::parse_date(date)?;
+
+    let style = input_with_default(
+        &format!(
+            "Enter the style of the ascent ({})",
+            Style::names().join(", "),
+        ),
+        ascent.style().name(),
+    );
+    let style = Style::new(&style)?;
 
-    let date = utils::input("Enter the date of the ascent in YYYY-MM-DD format: ");
-    let date = parse_date(date)?;
+    let notes = match ascent.notes() {
+        Some(notes) => input_with_default("Enter any notes on the ascent", notes),
+        None => utils::input("Enter any notes on the ascent (leave blank for none): "),
+    };
+    let notes = Some(notes).filter(|notes| !notes.is_empty());
 
-    Ok(Ascent::new(route, date))
+    Ok(Ascent::new(route, date, style).with_notes(notes))
+}
+
+// Offers to use a known crag in place of a new one that looks like a
+// misspelling of it, to avoid logging the same crag under two names
+fn resolve_crag(args: &Args, db: &AscentDB, route: Route) -> Result<Route> {
+    let crag = route.crag();
+    let known_crags = db.crags()?;
+
+    if known_crags.is_empty() || known_crags.contains(crag) {
+        return Ok(route);
+    }
+
+    let similar = utils::similar_names(crag, &known_crags);
+
+    let replacement = if similar.is_empty() {
+        println!("Warning: '{crag}' is not a known crag, so it will be added as a new one");
+
+        if !args.flag("yes") {
+            utils::confirm("Continue logging");
+        }
+
+        None
+    } else if args.flag("yes") {
+        Some(match_crag_without_prompt(crag, &similar)?)
+    } else {
+        println!("Warning: '{crag}' is not a known crag, did you mean:");
+
+        for (number, known) in similar.iter().enumerate() {
+            println!("{:>4}  {known}", number + 1);
+        }
+
+        let choice = utils::input(&format!(
+            "Enter the number of the crag to use, or nothing to add '{crag}' as a new crag: ",
+        ));
+
+        if choice.is_empty() {
+            None
+        } else {
+            match choice.parse::<usize>() {
+                Ok(number) if (1..=similar.len()).contains(&number) => Some(similar[number - 1]),
+                _ => return Err(Error::User(User::InvalidChoice)),
+            }
+        }
+    };
+
+    match replacement {
+        Some(known) => {
+            println!("Using the known crag '{known}'");
+
+            Route::new(
+                route.name().clone(),
+                route.grade().clone(),
+                route.scale().clone(),
+                known.clone(),
+            )
+        }
+        None => Ok(route),
+    }
+}
+
+// Without prompting, only names that differ in nothing but case, spacing or
+// punctuation are taken to be the same crag, while any other similar name
+// has to be settled by giving the exact name
+fn match_crag_without_prompt<'a>(crag: &str, similar: &[&'a String]) -> Result<&'a String> {
+    match similar
+        .iter()
+        .find(|known| utils::normalize_name(known) == utils::normalize_name(crag))
+    {
+        Some(known) => Ok(known),
+        None => Err(Error::User(User::SimilarCrag(
+            crag.to_string(),
+            similar.iter().map(|known| known.to_string()).collect(),
+        ))),
+    }
+}
+
+fn get_filter(args: &Args) -> Result<AscentFilter> {
+    let year = match args.value("year") {
+        Some(year) => Some(year.parse().map_err(|_| Error::User(User::InvalidYear))?),
+        None => None,
+    };
+
+    Ok(AscentFilter {
+        crag: args.value("crag").cloned(),
+        route: args.value("route").cloned(),
+        since: args
+            .value("since")
+            .cloned()
+            .map(utils::parse_date)
+            .transpose()?,
+        until: args
+            .value("until")
+            .cloned()
+            .map(utils::parse_date)
+            .transpose()?,
+        year,
+        grade_min: args.value("grade-min").cloned(),
+        grade_max: args.value("grade-max").cloned(),
+        scale: args.value("scale").cloned(),
+    })
+}
+
+// Picks one of the ascents of a route, by the date option if given and by
+// asking otherwise
+fn choose_ascent(args: &Args, mut ascents: Vec<Ascent>) -> Result<Ascent> {
+    if let Some(date) = args.value("date") {
+        let date = utils::parse_date(date.clone())?;
+
+        return match ascents.into_iter().find(|ascent| ascent.date() == date) {
+            Some(ascent) => Ok(ascent),
+            None => Err(Error::User(User::AscentNotFound)),
+        };
+    }
+
+    if ascents.len() == 1 {
+        return Ok(ascents.remove(0));
+    }
+
+    println!("Multiple ascents of that route were found:");
+
+    for (number, ascent) in ascents.iter().enumerate() {
+        println!("{:>4}  {ascent}", number + 1);
+    }
+
+    let choice = utils::input("Enter the number of the ascent: ");
+
+    match choice.parse::<usize>() {
+        Ok(number) if (1..=ascents.len()).contains(&number) => Ok(ascents.remove(number - 1)),
+        _ => Err(Error::User(User::InvalidChoice)),
+    }
 }
 
 fn init(args: Args) -> Result<()> {
-    println!("Initializing ascent database: {}", args.database);
-    init::init_ascent_db(&args.database)?;
+    println!("Initializing ascent database: {}", args.database());
+    init::init_ascent_db(args.database())?;
     println!("Successfully initialized database");
     Ok(())
 }
 
 fn log(args: Args) -> Result<()> {
-    let db = AscentDB::new(&args.database)?;
+    let db = AscentDB::new(args.database())?;
 
-    let ascent = get_ascent()?;
-    let crag = ascent.route().crag();
-    let known_crags = db.crags()?;
+    let route = get_route(&args)?;
+    let route = resolve_crag(&args, &db, route)?;
+    let ascent = get_ascent(&args, route)?;
 
-    if !known_crags.is_empty() && !known_crags.contains(crag) {
-        println!("Warning: '{crag}' is not a known crag");
-        println!("Known crags currently include:\n{}", known_crags.join("\n"));
-        utils::confirm("Continue logging");
+    match db.find_ascents(ascent.route()) {
+        Ok(previous) => {
+            let dates: Vec<String> = previous.iter().map(|a| a.date().to_string()).collect();
+            println!(
+                "Note: this route was previously sent on {}",
+                dates.join(", ")
+            );
+        }
+        Err(Error::User(User::AscentNotFound)) => (),
+        Err(e) => return Err(e),
     }
 
     println!("Ascent to be logged: {ascent}");
-    utils::confirm("Log the above ascent");
+
+    if !args.flag("yes") {
+        utils::confirm("Log the above ascent");
+    }
 
     db.log_ascent(&ascent)?;
     println!("Successfully logged the above ascent");
@@ -111,32 +933,237 @@ fn log(args: Args) -> Result<()> {
 }
 
 fn drop(args: Args) -> Result<()> {
-    let db = AscentDB::new(&args.database)?;
+    let db = AscentDB::new(args.database())?;
 
-    let route = get_route()?;
-    let ascent = db.find_ascent(route)?;
+    let route = get_route(&args)?;
+    let ascent = choose_ascent(&args, db.find_ascents(&route)?)?;
 
     println!("Ascent to be dropped: {ascent}");
     utils::confirm("Drop the above ascent");
 
-    db.drop_ascent(ascent.route())?;
+    db.drop_ascent(&ascent)?;
     println!("Successfully dropped the above ascent");
 
     Ok(())
 }
 
+fn edit(args: Args) -> Result<()> {
+    let db = AscentDB::new(args.database())?;
+
+    let route = get_route(&args)?;
+    let ascent = choose_ascent(&args, db.find_ascents(&route)?)?;
+
+    println!("Ascent to be edited: {ascent}");
+    println!("Enter a new value for each field, or nothing to keep the current value");
+
+    let edited = get_edited_ascent(&ascent)?;
+
+    if edited == ascent {
+        println!("No changes to save");
+        return Ok(());
+    }
+
+    println!("Ascent after editing: {edited}");
+    utils::confirm("Save the above changes");
+
+    db.update_ascent(&ascent, &edited)?;
+    println!("Successfully edited the ascent");
+
+    Ok(())
+}
+
+fn list(args: Args) -> Result<()> {
+    let filter = get_filter(&args)?;
+    let sort = match args.value("sort") {
+        Some(sort) => Sort::new(sort)?,
+        None => Sort::Date,
+    };
+
+    let ascents = list::list_ascent_db(args.database(), &filter, sort, args.flag("reverse"))?;
+    println!("{ascents}");
+    Ok(())
+}
+
+fn search(args: Args) -> Result<()> {
+    let ascents = list::search_ascent_db(args.database(), args.arg("text"))?;
+    println!("{ascents}");
+    Ok(())
+}
+
 fn analyze(args: Args) -> Result<()> {
-    let analysis = analyze::analyze_ascent_db(&args.database)?;
-    println!("{analysis}");
+    let format = match args.value("format") {
+        Some(format) => ReportFormat::new(format)?,
+        None => ReportFormat::Text,
+    };
+
+    let pyramid_grades = match args.value("pyramid-grades") {
+        Some(grades) => match grades.parse() {
+            Ok(grades) if grades > 0 => Some(grades),
+            _ => return Err(Error::User(User::InvalidPyramidGrades)),
+        },
+        None => None,
+    };
+
+    let period = match args.value("progression") {
+        Some(period) => Period::new(period)?,
+        None => Period::DEFAULT,
+    };
+
+    let calendar_year = match args.value("calendar") {
+        Some(year) => Some(year.parse().map_err(|_| Error::User(User::InvalidYear))?),
+        None => None,
+    };
+
+    let options = AnalyzeOptions {
+        pyramid_grades,
+        period,
+        calendar_year,
+    };
+
+    let filter = get_filter(&args)?;
+
+    if let Some(crosstab) = args.value("crosstab") {
+        let tables =
+            analyze::crosstab_ascent_db(args.database(), &filter, Crosstab::new(crosstab)?)?;
+        print!("{}", analyze::render_crosstabs(&tables, format));
+
+        return Ok(());
+    }
+
+    let report = analyze::analyze_ascent_db(args.database(), &filter, &options)?;
+    print!("{}", analyze::render_report(&report, format));
+
+    Ok(())
+}
+
+fn crag_rename(args: Args) -> Result<()> {
+    let db = AscentDB::new(args.database())?;
+    let (old, new) = (args.arg("old"), args.arg("new"));
+
+    if !args.flag("yes") {
+        utils::confirm(&format!("Rename crag '{old}' to '{new}'"));
+    }
+
+    let count = db.rename_crag(old, new)?;
+    println!("Successfully renamed the crag, changing the crag of {count} ascent(s)");
+
+    Ok(())
+}
+
+fn crag_merge(args: Args) -> Result<()> {
+    let db = AscentDB::new(args.database())?;
+    let (from, into) = (args.arg("from"), args.arg("into"));
+
+    if !args.flag("yes") {
+        utils::confirm(&format!("Merge crag '{from}' into '{into}'"));
+    }
+
+    let count = db.merge_crags(from, into)?;
+    println!("Successfully merged the crags, changing the crag of {count} ascent(s)");
+
+    Ok(())
+}
+
+fn get_format(args: &Args) -> Result<Format> {
+    match args.value("format") {
+        Some(format) => Format::new(format),
+        None => Ok(Format::Csv),
+    }
+}
+
+fn export(args: Args) -> Result<()> {
+    let exported = export::export_ascent_db(args.database(), get_format(&args)?)?;
+    print!("{exported}");
+    Ok(())
+}
+
+fn import(args: Args) -> Result<()> {
+    let crag_level = match args.value("crag-level") {
+        Some(level) => match level.parse() {
+            Ok(level) if level > 0 => Some(level),
+            _ => return Err(Error::User(User::InvalidCragLevel)),
+        },
+        None => None,
+    };
+
+    let options = ImportOptions {
+        leads_only: args.flag("leads-only"),
+        crag_level,
+        dry_run: args.flag("dry-run"),
+    };
+
+    let summary = import::import_ascent_db(
+        args.database(),
+        args.arg("file"),
+        get_format(&args)?,
+        &options,
+    )?;
+
+    if options.dry_run {
+        if !summary.imported.is_empty() {
+            println!("{}\n", list::make_ascents_table(&summary.imported));
+        }
+
+        println!(
+            "Would import {} ascent(s), but nothing was imported in a dry run",
+            summary.imported.len(),
+        );
+    } else {
+        println!("Successfully imported {} ascent(s)", summary.imported.len());
+    }
+
+    if summary.already_logged > 0 {
+        println!(
+            "Skipped {} ascent(s) that were already logged",
+            summary.already_logged,
+        );
+    }
+
+    if summary.left_out > 0 {
+        println!("Left out {} ascent(s) that weren't led", summary.left_out);
+    }
+
+    if !summary.unmapped.is_empty() {
+        println!(
+            "Skipped {} record(s) with no equivalent ascent:\n{}",
+            summary.unmapped.len(),
+            summary.unmapped.join("\n"),
+        );
+    }
+
+    Ok(())
+}
+
+fn migrate(args: Args) -> Result<()> {
+    println!("Migrating ascent database: {}", args.database());
+    let version = migrate::migrate_ascent_db(args.database())?;
+
+    if version == migrate::SCHEMA_VERSION {
+        println!("Database is already up to date");
+    } else {
+        println!(
+            "Successfully migrated database from schema version {version} to {}",
+            migrate::SCHEMA_VERSION,
+        );
+    }
+
     Ok(())
 }
 
 pub fn run(args: Args) -> Result<()> {
-    match args.subcommand {
+    match args.spec.subcommand {
         Subcommand::Init => init(args),
         Subcommand::Log => log(args),
         Subcommand::Drop => drop(args),
+        Subcommand::Edit => edit(args),
+        Subcommand::List => list(args),
+        Subcommand::Search => search(args),
         Subcommand::Analyze => analyze(args),
+        Subcommand::CragRename => crag_rename(args),
+        Subcommand::CragMerge => crag_merge(args),
+        Subcommand::Export => export(args),
+        Subcommand::Import => import(args),
+        Subcommand::Migrate => migrate(args),
     }
 }
 
@@ -150,7 +1177,11 @@ mod tests {
             "init".to_string(),
             "log".to_string(),
             "drop".to_string(),
+            "edit".to_string(),
+            "list".to_string(),
             "analyze".to_string(),
+            "export".to_string(),
+            "migrate".to_string(),
         ];
 
         for valid_subcommand in valid_subcommands {
@@ -165,6 +1196,54 @@ mod tests {
 
             assert!(result.is_ok());
         }
+
+        let result = Args::new(
+            [
+                "program".to_string(),
+                "analyze".to_string(),
+                "database".to_string(),
+                "--scale".to_string(),
+                "french".to_string(),
+            ]
+            .into_iter(),
+        );
+
+        assert_eq!(result.unwrap().value("scale"), Some(&"french".to_string()));
+
+        let args = Args::new(
+            [
+                "program",
+                "log",
+                "database",
+                "--route",
+                "Slither",
+                "--grade",
+                "5.7",
+                "--crag",
+                "Reimers Ranch",
+                "--date",
+                "2022-06-27",
+                "--yes",
+            ]
+            .map(String::from)
+            .into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(args.database(), "database");
+        assert_eq!(args.value("route"), Some(&"Slither".to_string()));
+        assert_eq!(args.value("grade"), Some(&"5.7".to_string()));
+        assert_eq!(args.value("scale"), None);
+        assert_eq!(args.value("crag"), Some(&"Reimers Ranch".to_string()));
+        assert_eq!(args.value("date"), Some(&"2022-06-27".to_string()));
+        assert_eq!(args.value("style"), None);
+        assert!(args.flag("yes"));
+
+        let ascent = get_ascent(&args, get_route(&args).unwrap()).unwrap();
+        assert_eq!(
+            ascent.to_string(),
+            "Slither 5.7 (YDS) at Reimers Ranch on 2022-06-27 (redpoint)",
+        );
     }
 
     #[test]
@@ -179,6 +1258,31 @@ mod tests {
                 "database".to_string(),
                 "extra".to_string(),
             ],
+            vec![
+                "program".to_string(),
+                "init".to_string(),
+                "database".to_string(),
+                "--scale".to_string(),
+                "french".to_string(),
+            ],
+            vec![
+                "program".to_string(),
+                "log".to_string(),
+                "database".to_string(),
+                "--route".to_string(),
+            ],
+            vec![
+                "program".to_string(),
+                "analyze".to_string(),
+                "database".to_string(),
+                "--yes".to_string(),
+            ],
+            vec![
+                "program".to_string(),
+                "analyze".to_string(),
+                "database".to_string(),
+                "--scale".to_string(),
+            ],
         ];
 
         for invalid_arg_set in invalid_arg_sets {
@@ -186,4 +1290,129 @@ mod tests {
             assert!(result.is_err());
         }
     }
+
+    fn parse(args: &[&str]) -> Result<Parsed> {
+        Args::parse(["program"].iter().chain(args).map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn crag_without_prompt() {
+        let known_crags = ["Reimers Ranch".to_string(), "Enchanted Rock".to_string()];
+        let similar = |crag| utils::similar_names(crag, &known_crags);
+
+        assert_eq!(
+            match_crag_without_prompt("reimers-ranch", &similar("reimers-ranch")).unwrap(),
+            "Reimers Ranch",
+        );
+
+        // A near miss is neither taken as the known crag nor added as a new
+        // one without asking
+        let error =
+            match_crag_without_prompt("Reimer Ranch", &similar("Reimer Ranch")).unwrap_err();
+
+        assert_eq!(
+            error,
+            Error::User(User::SimilarCrag(
+                "Reimer Ranch".to_string(),
+                vec!["Reimers Ranch".to_string()],
+            )),
+        );
+        assert!(error
+            .to_string()
+            .starts_with("'Reimer Ranch' is not a known crag, did you mean:\nReimers Ranch\n"));
+    }
+
+    #[test]
+    fn option_forms() {
+        let Parsed::Args(args) = parse(&["log", "--crag=Reimers Ranch", "-y", "database"]).unwrap()
+        else {
+            panic!("expected args");
+        };
+
+        assert_eq!(args.database(), "database");
+        assert_eq!(args.value("crag"), Some(&"Reimers Ranch".to_string()));
+        assert!(args.flag("yes"));
+
+        let Parsed::Args(args) = parse(&["init", "--", "-database"]).unwrap() else {
+            panic!("expected args");
+        };
+
+        assert_eq!(args.database(), "-database");
+    }
+
+    #[test]
+    fn help_and_version() {
+        for help_args in [&["--help"][..], &["-h"], &["help"]] {
+            let Parsed::Help(text) = parse(help_args).unwrap() else {
+                panic!("expected help");
+            };
+
+            for spec in &SUBCOMMANDS {
+                assert!(text.contains(spec.about));
+            }
+        }
+
+        for help_args in [
+            &["help", "log"][..],
+            &["log", "--help"],
+            &["log", "db", "-h"],
+        ] {
+            let Parsed::Help(text) = parse(help_args).unwrap() else {
+                panic!("expected help");
+            };
+
+            assert!(text.contains("Usage: ascents log database [options]"));
+            assert!(text.contains("-y, --yes"));
+            assert!(text.contains("--date YYYY-MM-DD"));
+        }
+
+        let Parsed::Help(text) = parse(&["help", "crag", "rename"]).unwrap() else {
+            panic!("expected help");
+        };
+
+        assert!(text.contains("Usage: ascents crag rename database old new [options]"));
+
+        assert!(matches!(parse(&["--version"]).unwrap(), Parsed::Version));
+        assert!(matches!(parse(&["-V"]).unwrap(), Parsed::Version));
+    }
+
+    #[test]
+    fn arg_errors() {
+        let error = |args: &[&str]| match parse(args) {
+            Err(Error::User(e)) => e,
+            _ => panic!("expected a user error"),
+        };
+
+        assert_eq!(
+            error(&["help", "climb"]),
+            User::InvalidSubcommand("climb".to_string()),
+        );
+        assert_eq!(
+            error(&["log", "db", "--rout", "Slither"]),
+            User::InvalidOption("--rout".to_string(), "log"),
+        );
+        assert_eq!(
+            error(&["log", "db", "--yes=no"]),
+            User::InvalidOption("--yes=no".to_string(), "log"),
+        );
+        assert_eq!(
+            error(&["analyze", "db", "--scale"]),
+            User::MissingOptionValue("--scale".to_string()),
+        );
+        assert_eq!(
+            error(&["drop", "db", "extra"]),
+            User::UnexpectedArg("extra".to_string()),
+        );
+        assert_eq!(error(&["migrate"]), User::MissingArg("database"));
+        assert_eq!(error(&["search", "db"]), User::MissingArg("text"));
+        assert_eq!(error(&["crag"]), User::MissingArg("subcommand"));
+        assert_eq!(
+            error(&["crag", "split", "db"]),
+            User::InvalidSubcommand("crag split".to_string()),
+        );
+        assert_eq!(
+            error(&["crag", "merge", "db", "A"]),
+            User::MissingArg("into")
+        );
+    }
 }
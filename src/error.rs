@@ -1,3 +1,7 @@
+use crate::{
+    grades, migrate,
+    models::{Crosstab, Format, Period, ReportFormat, Sort, Style},
+};
 use std::{fmt, result};
 
 pub type Result<T> = result::Result<T, Error>;
@@ -25,37 +29,139 @@ impl From<rusqlite::Error> for Error {
 
 #[derive(Debug, PartialEq)]
 pub enum User {
-    InvalidGrade,
+    InvalidGrade(&'static str),
+    InvalidScale,
+    InvalidStyle,
+    InvalidSort,
+    InvalidPeriod,
+    InvalidCrosstab,
     InvalidDate,
+    InvalidYear,
+    CalendarYearMismatch,
+    InvalidCragLevel,
+    InvalidPyramidGrades,
+    InvalidFormat,
+    CannotExport(&'static str),
+    InvalidReportFormat,
     DatabaseNotFound,
     DatabaseAlreadyExists,
+    DatabaseNeedsMigration,
+    DatabaseTooNew(u32),
     MissingArg(&'static str),
-    InvalidSubcommand,
-    TooManyArgs,
+    InvalidSubcommand(String),
+    UnexpectedArg(String),
+    InvalidOption(String, &'static str),
+    MissingOptionValue(String),
     AscentAlreadyLogged(String),
     AscentNotFound,
+    CragNotFound(String),
+    CragAlreadyExists(String),
+    CragMergeConflict(Vec<String>),
+    SameCrag,
+    SimilarCrag(String, Vec<String>),
+    InvalidChoice,
+    FileNotFound(String),
+    MissingColumn(&'static str),
+    InvalidImport(Vec<String>),
 }
 
 impl fmt::Display for User {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            User::InvalidGrade => write!(
+            User::InvalidGrade(scale) => write!(f, "grade must be a valid {scale} grade"),
+            User::InvalidScale => write!(
                 f,
-                "grade must be in YDS with no pluses, minuses, or slashes",
+                "scale must be one of: {}",
+                grades::scale_names().join(", "),
+            ),
+            User::InvalidStyle => write!(f, "style must be one of: {}", Style::names().join(", ")),
+            User::InvalidSort => write!(f, "sort must be one of: {}", Sort::names().join(", ")),
+            User::InvalidCrosstab => write!(
+                f,
+                "crosstab must be one of: {}",
+                Crosstab::names().join(", "),
+            ),
+            User::InvalidPeriod => write!(
+                f,
+                "period must be one of: {}",
+                Period::names().join(", "),
             ),
             User::InvalidDate => write!(f, "date must be a valid date in YYYY-MM-DD format"),
+            User::InvalidYear => write!(f, "year must be a valid year, such as 2023"),
+            User::CalendarYearMismatch => {
+                write!(f, "calendar year must be the same as the year analyzed")
+            }
+            User::InvalidCragLevel => write!(f, "crag level must be a positive number"),
+            User::InvalidPyramidGrades => {
+                write!(f, "number of pyramid grades must be a positive number")
+            }
+            User::InvalidFormat => write!(
+                f,
+                "format must be one of: {}",
+                Format::names().join(", "),
+            ),
+            User::InvalidReportFormat => write!(
+                f,
+                "format must be one of: {}",
+                ReportFormat::names().join(", "),
+            ),
+            User::CannotExport(format) => {
+                write!(f, "'{format}' is an import-only format and can't be exported")
+            }
             User::DatabaseNotFound => write!(
                 f,
                 "database not found, must be an already initialized ascent database",
             ),
             User::DatabaseAlreadyExists => write!(f, "Cannot initialize database, already exists"),
+            User::DatabaseNeedsMigration => write!(
+                f,
+                "database uses an older schema, run `ascents migrate` on it first",
+            ),
+            User::DatabaseTooNew(version) => write!(
+                f,
+                "database schema version {version} is newer than the latest supported version {}, \
+                upgrade ascents to use it",
+                migrate::SCHEMA_VERSION,
+            ),
             User::MissingArg(arg) => write!(f, "Must provide {arg}"),
-            User::InvalidSubcommand => write!(f, "Invalid subcommand"),
-            User::TooManyArgs => write!(f, "Too many args provided"),
+            User::InvalidSubcommand(subcommand) => write!(f, "Invalid subcommand: {subcommand}"),
+            User::UnexpectedArg(arg) => write!(f, "Unexpected arg: {arg}"),
+            User::InvalidOption(option, subcommand) => write!(
+                f,
+                "Invalid option for {subcommand}: {option}, \
+                see `ascents help {subcommand}` for its options",
+            ),
+            User::MissingOptionValue(option) => write!(f, "Option {option} requires a value"),
             User::AscentAlreadyLogged(date) => {
-                write!(f, "That ascent was already logged with a date of {date}")
+                write!(f, "An ascent of that route was already logged on {date}")
             }
             User::AscentNotFound => write!(f, "No ascent found matching provided route"),
+            User::CragNotFound(crag) => write!(f, "No crag found named '{crag}'"),
+            User::CragAlreadyExists(crag) => write!(
+                f,
+                "A crag named '{crag}' already exists, merge into it with `ascents crag merge` instead",
+            ),
+            User::CragMergeConflict(ascents) => write!(
+                f,
+                "Merging would log the same route twice on one day, \
+                drop or edit one of each of these first:\n{}",
+                ascents.join("\n"),
+            ),
+            User::SameCrag => write!(f, "Cannot merge a crag into itself"),
+            User::SimilarCrag(crag, similar) => write!(
+                f,
+                "'{crag}' is not a known crag, did you mean:\n{}\n\
+                Give the exact name with --crag, or leave out --yes to add it as a new crag",
+                similar.join("\n"),
+            ),
+            User::InvalidChoice => write!(f, "Choice must be one of the listed numbers"),
+            User::FileNotFound(file) => write!(f, "Cannot read file: {file}"),
+            User::MissingColumn(column) => write!(f, "File must have a {column} column"),
+            User::InvalidImport(errors) => write!(
+                f,
+                "Nothing was imported, fix these lines first:\n{}",
+                errors.join("\n"),
+            ),
         }
     }
 }
@@ -0,0 +1,87 @@
+use crate::{
+    error::{Error, Result, User},
+    models::{Ascent, AscentDB, AscentFilter, Format, Sort},
+};
+
+// Columns of exported ascents, which import expects too
+pub const COLUMNS: [&str; 7] = ["route", "grade", "scale", "crag", "date", "style", "notes"];
+
+fn ascent_fields(ascent: &Ascent) -> [String; 7] {
+    let route = ascent.route();
+
+    [
+        route.name().clone(),
+        route.grade().clone(),
+        route.scale().clone(),
+        route.crag().clone(),
+        ascent.date().to_string(),
+        ascent.style().to_string(),
+        ascent.notes().cloned().unwrap_or_default(),
+    ]
+}
+
+pub fn write_csv(ascents: &[Ascent]) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer
+        .write_record(COLUMNS)
+        .expect("Should be able to write CSV to memory");
+
+    for ascent in ascents {
+        writer
+            .write_record(ascent_fields(ascent))
+            .expect("Should be able to write CSV to memory");
+    }
+
+    let csv = writer
+        .into_inner()
+        .expect("Should be able to write CSV to memory");
+
+    String::from_utf8(csv).expect("CSV of strings should be valid UTF-8")
+}
+
+pub fn write_json(ascents: &[Ascent]) -> String {
+    serde_json::to_string_pretty(ascents).expect("Ascents should serialize to JSON") + "\n"
+}
+
+pub fn write_jsonl(ascents: &[Ascent]) -> String {
+    ascents
+        .iter()
+        .map(|ascent| {
+            serde_json::to_string(ascent).expect("Ascents should serialize to JSON") + "\n"
+        })
+        .collect()
+}
+
+pub fn export_ascent_db(database: &String, format: Format) -> Result<String> {
+    let write = match format {
+        Format::Csv => write_csv,
+        Format::Json => write_json,
+        Format::Jsonl => write_jsonl,
+        Format::MountainProject | Format::TheCrag | Format::EightA => {
+            return Err(Error::User(User::CannotExport(format.name())));
+        }
+    };
+
+    let db = AscentDB::new(database)?;
+    let ascents = db.list_ascents(&AscentFilter::default(), Sort::Date)?;
+    let exported = write(&ascents);
+
+    Ok(exported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_only_formats() {
+        let error = export_ascent_db(&"ascent.db".to_string(), Format::TheCrag).unwrap_err();
+
+        assert_eq!(error, Error::User(User::CannotExport("thecrag")));
+        assert_eq!(
+            error.to_string(),
+            "'thecrag' is an import-only format and can't be exported",
+        );
+    }
+}
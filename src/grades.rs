@@ -0,0 +1,607 @@
+pub struct Grade {
+    value: String,
+    number: u32,
+    letter: Option<String>,
+    difficulty: f64,
+}
+
+impl Grade {
+    fn new(value: String, number: u32, letter: Option<&str>, difficulty: f64) -> Self {
+        Self {
+            value,
+            number,
+            letter: letter.map(str::to_string),
+            difficulty,
+        }
+    }
+
+    pub fn value(&self) -> &String {
+        &self.value
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn letter(&self) -> Option<&String> {
+        self.letter.as_ref()
+    }
+
+    /// Index of how hard the grade is, comparable across all scales of
+    /// the same discipline. For routes, 5.10a has a difficulty of 10 and
+    /// each YDS letter grade above it adds 1. For boulders, the
+    /// difficulty matches the V-scale number.
+    pub fn difficulty(&self) -> f64 {
+        self.difficulty
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Discipline {
+    Route,
+    Boulder,
+}
+
+impl Discipline {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Discipline::Route => "route",
+            Discipline::Boulder => "boulder",
+        }
+    }
+}
+
+pub trait GradeScale {
+    /// Identifier of the scale as stored in the database
+    fn name(&self) -> &'static str;
+
+    /// Name of the scale as shown to the user
+    fn label(&self) -> &'static str;
+
+    /// Kind of climbing the scale grades
+    fn discipline(&self) -> Discipline {
+        Discipline::Route
+    }
+
+    /// Every grade in the scale, ordered from easiest to hardest
+    fn grades(&self) -> Vec<Grade>;
+
+    fn is_valid(&self, grade: &str) -> bool {
+        self.grades().iter().any(|g| g.value == grade)
+    }
+
+    /// Grade that an alias grade, such as 5.10+ in YDS, is counted as when
+    /// grouping grades, or the grade itself if it isn't an alias
+    fn canonical_grade(&self, grade: &str) -> Option<String> {
+        self.is_valid(grade).then(|| grade.to_string())
+    }
+}
+
+// Registry of supported scales. The order here is the order in which
+// scales are reported, so route scales come before boulder scales.
+pub const SCALES: [&dyn GradeScale; 7] = [&Yds, &French, &Uiaa, &Ewbank, &British, &VScale, &Font];
+
+pub const DEFAULT_SCALE: &str = "yds";
+
+pub fn find_scale(name: &str) -> Option<&'static dyn GradeScale> {
+    SCALES.into_iter().find(|scale| scale.name() == name)
+}
+
+pub fn scale_names() -> Vec<&'static str> {
+    SCALES.iter().map(|scale| scale.name()).collect()
+}
+
+/// Converts a grade to the grade of another scale with the closest
+/// difficulty, preferring the easier grade on ties. Returns `None` if the
+/// grade is not valid or the scales grade different disciplines.
+pub fn convert(grade: &str, from: &dyn GradeScale, to: &dyn GradeScale) -> Option<String> {
+    if from.discipline() != to.discipline() {
+        return None;
+    }
+
+    // Several grades of a scale can share a difficulty (e.g., 5.11- and
+    // 5.11a/b), so a grade always converts to itself within its own scale
+    if from.name() == to.name() {
+        return from.is_valid(grade).then(|| grade.to_string());
+    }
+
+    let difficulty = from
+        .grades()
+        .into_iter()
+        .find(|g| g.value == grade)?
+        .difficulty;
+
+    let mut closest: Option<Grade> = None;
+
+    for candidate in to.grades() {
+        let is_closer = match &closest {
+            None => true,
+            Some(current) => {
+                (candidate.difficulty - difficulty).abs() < (current.difficulty - difficulty).abs()
+            }
+        };
+
+        if is_closer {
+            closest = Some(candidate);
+        }
+    }
+
+    closest.map(|grade| grade.value)
+}
+
+fn round_difficulty(difficulty: f64) -> f64 {
+    (difficulty * 10.0).round() / 10.0
+}
+
+pub struct Yds;
+
+// Suffixes of 5.10 and up, from easiest to hardest, along with their
+// difficulty relative to the "a" grade. A plus or minus (or no suffix at
+// all) is ranked alongside the slash grade it is roughly equivalent to.
+const YDS_UPPER_SUFFIXES: [(&str, f64); 10] = [
+    ("a", 0.0),
+    ("-", 0.5),
+    ("a/b", 0.5),
+    ("b", 1.0),
+    ("", 1.5),
+    ("b/c", 1.5),
+    ("c", 2.0),
+    ("+", 2.5),
+    ("c/d", 2.5),
+    ("d", 3.0),
+];
+
+// Suffixes of 5.9 and below, from easiest to hardest
+const YDS_LOWER_SUFFIXES: [(&str, f64); 3] = [("-", -0.3), ("", 0.0), ("+", 0.3)];
+
+impl GradeScale for Yds {
+    fn name(&self) -> &'static str {
+        "yds"
+    }
+
+    fn label(&self) -> &'static str {
+        "YDS"
+    }
+
+    fn grades(&self) -> Vec<Grade> {
+        let mut grades = Vec::new();
+
+        for number in 0..=15 {
+            let suffixes = if number < 10 {
+                &YDS_LOWER_SUFFIXES[..]
+            } else {
+                &YDS_UPPER_SUFFIXES[..]
+            };
+
+            let base = if number < 10 {
+                number as f64
+            } else {
+                10.0 + 4.0 * (number - 10) as f64
+            };
+
+            for (suffix, offset) in suffixes {
+                grades.push(Grade::new(
+                    format!("5.{number}{suffix}"),
+                    number,
+                    (!suffix.is_empty()).then_some(suffix),
+                    base + offset,
+                ));
+            }
+        }
+
+        grades
+    }
+
+    // Grades below 5.10 go without their + or -, while grades from 5.10 up
+    // go by letter, with aliases halfway between two letters counting as
+    // the easier one
+    fn canonical_grade(&self, grade: &str) -> Option<String> {
+        let grade = self.grades().into_iter().find(|g| g.value == grade)?;
+
+        let letter = match (grade.number, grade.letter.as_deref()) {
+            (0..=9, _) => "",
+            (_, Some("-" | "a" | "a/b")) => "a",
+            (_, None | Some("b" | "b/c")) => "b",
+            (_, Some("+" | "c" | "c/d")) => "c",
+            _ => "d",
+        };
+
+        Some(format!("5.{}{letter}", grade.number))
+    }
+}
+
+pub struct French;
+
+impl GradeScale for French {
+    fn name(&self) -> &'static str {
+        "french"
+    }
+
+    fn label(&self) -> &'static str {
+        "French"
+    }
+
+    fn grades(&self) -> Vec<Grade> {
+        let mut grades = Vec::new();
+
+        for number in 1..=3 {
+            grades.push(Grade::new(number.to_string(), number, None, number as f64));
+        }
+
+        // Position of the grade counting up from 4a
+        let mut position = 0;
+
+        for number in 4..=9 {
+            for letter in 'a'..='c' {
+                for plus in ["", "+"] {
+                    let difficulty = match position {
+                        // 4a through 5c+ in half steps, 5c lining up with 5.9
+                        0..=11 => 4.0 + position as f64 * 0.5,
+                        // 6a and 6a+ line up with 5.10a and 5.10b
+                        12 | 13 => position as f64 - 2.0,
+                        // 6b and up line up with 5.10d and up
+                        _ => position as f64 - 1.0,
+                    };
+
+                    let letter = format!("{letter}{plus}");
+                    grades.push(Grade::new(
+                        format!("{number}{letter}"),
+                        number,
+                        Some(&letter),
+                        difficulty,
+                    ));
+
+                    position += 1;
+                }
+            }
+        }
+
+        grades
+    }
+}
+
+pub struct Uiaa;
+
+const UIAA_NUMERALS: [&str; 12] = [
+    "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII",
+];
+
+impl GradeScale for Uiaa {
+    fn name(&self) -> &'static str {
+        "uiaa"
+    }
+
+    fn label(&self) -> &'static str {
+        "UIAA"
+    }
+
+    fn grades(&self) -> Vec<Grade> {
+        let mut grades = Vec::new();
+
+        for (number, numeral) in (1..).zip(UIAA_NUMERALS) {
+            for modifier in ["-", "", "+"] {
+                // VI+ lines up with 5.10a, with grades below it in whole
+                // steps and grades above it spread out to reach 5.15c/d
+                let position = grades.len() as f64;
+                let difficulty = if position >= 17.0 {
+                    round_difficulty(10.0 + (position - 17.0) * 1.3)
+                } else {
+                    (position - 7.0).max(0.0)
+                };
+
+                grades.push(Grade::new(
+                    format!("{numeral}{modifier}"),
+                    number,
+                    (!modifier.is_empty()).then_some(modifier),
+                    difficulty,
+                ));
+            }
+        }
+
+        grades
+    }
+}
+
+pub struct Ewbank;
+
+impl GradeScale for Ewbank {
+    fn name(&self) -> &'static str {
+        "ewbank"
+    }
+
+    fn label(&self) -> &'static str {
+        "Ewbank"
+    }
+
+    fn grades(&self) -> Vec<Grade> {
+        (1..=39)
+            .map(|number| {
+                // 18 lines up with 5.10a, with 24 covering both 5.11c and 5.11d
+                let difficulty = if number <= 23 {
+                    number as f64 - 8.0
+                } else {
+                    number as f64 - 7.0
+                };
+
+                Grade::new(number.to_string(), number, None, difficulty.max(0.0))
+            })
+            .collect()
+    }
+}
+
+pub struct British;
+
+// Adjectival grades below E1, from easiest to hardest, with their
+// difficulty
+const BRITISH_ADJECTIVAL: [(&str, f64); 8] = [
+    ("M", 1.0),
+    ("D", 2.0),
+    ("VD", 3.0),
+    ("HVD", 4.0),
+    ("S", 5.0),
+    ("HS", 6.0),
+    ("VS", 7.0),
+    ("HVS", 8.5),
+];
+
+// Difficulty of E1 through E11. E-grades also depend on how bold a route
+// is, so these are only a rough guide.
+const BRITISH_E_DIFFICULTY: [f64; 11] = [
+    10.0, 11.5, 13.0, 15.0, 17.0, 19.0, 21.0, 23.0, 25.0, 27.0, 29.0,
+];
+
+impl GradeScale for British {
+    fn name(&self) -> &'static str {
+        "british"
+    }
+
+    fn label(&self) -> &'static str {
+        "British"
+    }
+
+    fn grades(&self) -> Vec<Grade> {
+        let mut grades = Vec::new();
+
+        for (adjectival, difficulty) in BRITISH_ADJECTIVAL {
+            grades.push(Grade::new(
+                adjectival.to_string(),
+                0,
+                Some(adjectival),
+                difficulty,
+            ));
+        }
+
+        for (number, difficulty) in (1..).zip(BRITISH_E_DIFFICULTY) {
+            grades.push(Grade::new(format!("E{number}"), number, None, difficulty));
+        }
+
+        grades
+    }
+}
+
+pub struct VScale;
+
+impl GradeScale for VScale {
+    fn name(&self) -> &'static str {
+        "v"
+    }
+
+    fn label(&self) -> &'static str {
+        "V-scale"
+    }
+
+    fn discipline(&self) -> Discipline {
+        Discipline::Boulder
+    }
+
+    fn grades(&self) -> Vec<Grade> {
+        let mut grades = vec![Grade::new("VB".to_string(), 0, Some("B"), -1.0)];
+
+        for number in 0..=17 {
+            grades.push(Grade::new(
+                format!("V{number}"),
+                number,
+                None,
+                number as f64,
+            ));
+        }
+
+        grades
+    }
+}
+
+pub struct Font;
+
+// Grades below 6A, from easiest to hardest
+const FONT_LOWER: [(&str, u32, Option<&str>, f64); 7] = [
+    ("3", 3, None, -2.0),
+    ("4-", 4, Some("-"), -1.5),
+    ("4", 4, None, -1.0),
+    ("4+", 4, Some("+"), 0.0),
+    ("5-", 5, Some("-"), 0.5),
+    ("5", 5, None, 1.0),
+    ("5+", 5, Some("+"), 2.0),
+];
+
+// Difficulty of 6A through 8C+, from easiest to hardest
+const FONT_UPPER_DIFFICULTY: [f64; 18] = [
+    3.0, 3.5, 4.0, 4.5, 5.0, 5.5, 6.0, 7.0, 8.0, 8.5, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+];
+
+impl GradeScale for Font {
+    fn name(&self) -> &'static str {
+        "font"
+    }
+
+    fn label(&self) -> &'static str {
+        "Font"
+    }
+
+    fn discipline(&self) -> Discipline {
+        Discipline::Boulder
+    }
+
+    fn grades(&self) -> Vec<Grade> {
+        let mut grades = Vec::new();
+
+        for (value, number, letter, difficulty) in FONT_LOWER {
+            grades.push(Grade::new(value.to_string(), number, letter, difficulty));
+        }
+
+        let mut upper_difficulty = FONT_UPPER_DIFFICULTY.into_iter();
+
+        for number in 6..=8 {
+            for letter in 'A'..='C' {
+                for plus in ["", "+"] {
+                    let letter = format!("{letter}{plus}");
+                    grades.push(Grade::new(
+                        format!("{number}{letter}"),
+                        number,
+                        Some(&letter),
+                        upper_difficulty.next().expect("Should have a difficulty"),
+                    ));
+                }
+            }
+        }
+
+        grades.push(Grade::new("9A".to_string(), 9, Some("A"), 17.0));
+
+        grades
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn scale_names_are_unique() {
+        let names: HashSet<_> = scale_names().into_iter().collect();
+        assert_eq!(names.len(), SCALES.len());
+    }
+
+    #[test]
+    fn grades_are_unique_within_scale() {
+        for scale in SCALES {
+            let grades = scale.grades();
+            let values: HashSet<_> = grades.iter().map(|grade| grade.value()).collect();
+            assert_eq!(values.len(), grades.len(), "{}", scale.name());
+        }
+    }
+
+    #[test]
+    fn route_scales_before_boulder_scales() {
+        let first_boulder = SCALES
+            .iter()
+            .position(|scale| scale.discipline() == Discipline::Boulder)
+            .unwrap();
+
+        assert!(SCALES[first_boulder..]
+            .iter()
+            .all(|scale| scale.discipline() == Discipline::Boulder));
+    }
+
+    #[test]
+    fn difficulty_increases_within_scale() {
+        for scale in SCALES {
+            let grades = scale.grades();
+
+            for pair in grades.windows(2) {
+                assert!(
+                    pair[0].difficulty() <= pair[1].difficulty(),
+                    "{} {}",
+                    scale.name(),
+                    pair[1].value(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let conversions = [
+            ("yds", "5.12a", "french", "7a+"),
+            ("yds", "5.10a", "uiaa", "VI+"),
+            ("yds", "5.11a/b", "french", "6b+"),
+            ("french", "6a", "yds", "5.10a"),
+            ("french", "8a", "ewbank", "30"),
+            ("ewbank", "25", "yds", "5.12a"),
+            ("british", "E5", "yds", "5.11d"),
+            ("v", "V4", "font", "6B"),
+            ("font", "7A", "v", "V6"),
+            ("yds", "5.9+", "yds", "5.9+"),
+            ("yds", "5.11a/b", "yds", "5.11a/b"),
+        ];
+
+        for (from, grade, to, expected) in conversions {
+            let converted = convert(grade, find_scale(from).unwrap(), find_scale(to).unwrap());
+            assert_eq!(converted.as_deref(), Some(expected), "{grade} to {to}");
+        }
+    }
+
+    #[test]
+    fn no_conversion_across_disciplines() {
+        let converted = convert("V4", &VScale, &Yds);
+        assert_eq!(converted, None);
+    }
+
+    #[test]
+    fn canonical_grades() {
+        let canonical = |grade| Yds.canonical_grade(grade);
+
+        assert_eq!(canonical("5.7+").as_deref(), Some("5.7"));
+        assert_eq!(canonical("5.10-").as_deref(), Some("5.10a"));
+        assert_eq!(canonical("5.10").as_deref(), Some("5.10b"));
+        assert_eq!(canonical("5.10+").as_deref(), Some("5.10c"));
+        assert_eq!(canonical("5.10c/d").as_deref(), Some("5.10c"));
+        assert_eq!(canonical("5.10d").as_deref(), Some("5.10d"));
+        assert_eq!(canonical("5.16a"), None);
+
+        // Every grade of other scales stands for itself
+        assert_eq!(French.canonical_grade("6a+").as_deref(), Some("6a+"));
+    }
+
+    #[test]
+    fn valid_grades() {
+        let valid_grades = [
+            ("yds", "5.10a"),
+            ("yds", "5.11a/b"),
+            ("french", "6a"),
+            ("french", "9c+"),
+            ("uiaa", "VII+"),
+            ("ewbank", "24"),
+            ("british", "HVS"),
+            ("british", "E5"),
+            ("v", "VB"),
+            ("v", "V17"),
+            ("font", "5+"),
+            ("font", "7A+"),
+            ("font", "9A"),
+        ];
+
+        for (scale, grade) in valid_grades {
+            assert!(find_scale(scale).unwrap().is_valid(grade), "{grade}");
+        }
+    }
+
+    #[test]
+    fn invalid_grades() {
+        let invalid_grades = [
+            ("yds", "6a"),
+            ("french", "5.10a"),
+            ("french", "6d"),
+            ("uiaa", "XIII"),
+            ("ewbank", "40"),
+            ("british", "E12"),
+            ("v", "V18"),
+            ("v", "v5"),
+            ("font", "7a"),
+            ("font", "9A+"),
+        ];
+
+        for (scale, grade) in invalid_grades {
+            assert!(!find_scale(scale).unwrap().is_valid(grade), "{grade}");
+        }
+    }
+}
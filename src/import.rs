@@ -0,0 +1,875 @@
+use crate::{
+    error::{Error, Result, User},
+    export,
+    grades::{self, Discipline},
+    models::{Ascent, AscentDB, Format, Route, Style},
+    utils,
+};
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    result,
+};
+
+// Builds an ascent from the fields of one imported record, where only the
+// route, grade, crag and date are required
+fn ascent_from_fields(fields: [Option<&str>; 7]) -> Result<Ascent> {
+    let [route, grade, scale, crag, date, style, notes] =
+        fields.map(|field| field.map(str::trim).filter(|field| !field.is_empty()));
+
+    let required = |field: Option<&str>, name| match field {
+        Some(field) => Ok(field.to_string()),
+        None => Err(Error::User(User::MissingArg(name))),
+    };
+
+    let route = Route::new(
+        required(route, "route")?,
+        required(grade, "grade")?,
+        scale.unwrap_or(grades::DEFAULT_SCALE).to_string(),
+        required(crag, "crag")?,
+    )?;
+
+    let date = utils::parse_date(required(date, "date")?)?;
+
+    let style = match style {
+        Some(style) => Style::new(style)?,
+        None => Style::DEFAULT,
+    };
+
+    Ok(Ascent::new(route, date, style).with_notes(notes.map(String::from)))
+}
+
+/// Reads ascents from CSV with a header row naming the columns, which can
+/// be in any order. Every line is checked before returning, with an error
+/// listing each bad line if there are any.
+pub fn read_csv(reader: impl Read) -> Result<Vec<Ascent>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| Error::User(User::InvalidImport(vec![format!("line 1: {e}")])))?
+        .clone();
+
+    let indices = column_indices(
+        &headers,
+        export::COLUMNS,
+        &["route", "grade", "crag", "date"],
+    )?;
+
+    let mut ascents = Vec::new();
+    let mut errors = Vec::new();
+
+    for record in reader.records() {
+        let result = record.map_err(|e| e.to_string()).and_then(|record| {
+            let line = record.position().map_or(0, |position| position.line());
+            let fields = indices.map(|index| index.and_then(|index| record.get(index)));
+
+            ascent_from_fields(fields).map_err(|e| format!("line {line}: {e}"))
+        });
+
+        match result {
+            Ok(ascent) => ascents.push(ascent),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::User(User::InvalidImport(errors)));
+    }
+
+    Ok(ascents)
+}
+
+/// Reads ascents from a JSON array of them, in the same shape they are
+/// exported in. Every ascent is checked before returning, with an error
+/// listing each bad one if there are any.
+pub fn read_json(reader: impl Read) -> Result<Vec<Ascent>> {
+    let values: Vec<serde_json::Value> = serde_json::from_reader(reader)
+        .map_err(|e| Error::User(User::InvalidImport(vec![e.to_string()])))?;
+
+    let mut ascents = Vec::new();
+    let mut errors = Vec::new();
+
+    for (number, value) in values.into_iter().enumerate() {
+        match serde_json::from_value(value) {
+            Ok(ascent) => ascents.push(ascent),
+            Err(e) => errors.push(format!("ascent {}: {e}", number + 1)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::User(User::InvalidImport(errors)));
+    }
+
+    Ok(ascents)
+}
+
+/// Reads ascents from JSON Lines, with one ascent per line in the same shape
+/// they are exported in and blank lines ignored
+pub fn read_jsonl(reader: impl Read) -> Result<Vec<Ascent>> {
+    let mut ascents = Vec::new();
+    let mut errors = Vec::new();
+
+    for (number, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.map_err(|e| Error::User(User::InvalidImport(vec![e.to_string()])))?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(&line) {
+            Ok(ascent) => ascents.push(ascent),
+            Err(e) => errors.push(format!("line {}: {e}", number + 1)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::User(User::InvalidImport(errors)));
+    }
+
+    Ok(ascents)
+}
+
+/// Options for imports from other logbooks
+#[derive(Debug, Default)]
+pub struct ImportOptions {
+    // Leave out ascents that weren't led, such as topropes and follows
+    pub leads_only: bool,
+    // Part of a location path to use as the crag, counting from 1 at the top
+    pub crag_level: Option<usize>,
+    // Check what would be imported without logging anything
+    pub dry_run: bool,
+}
+
+/// Ascents read from another logbook, along with what was left behind
+#[derive(Debug, Default, PartialEq)]
+pub struct Imported {
+    pub ascents: Vec<Ascent>,
+    // Number of records left out by the import options
+    pub left_out: usize,
+    // Records that have no equivalent ascent, with the reason why
+    pub unmapped: Vec<String>,
+}
+
+// A record from another logbook with its grade and style mapped, where a
+// grade of None has no equivalent
+struct Tick<'a> {
+    route: &'a str,
+    grade: &'a str,
+    mapped_grade: Option<(String, &'static str)>,
+    crag: &'a str,
+    date: &'a str,
+    style: result::Result<Style, String>,
+    notes: &'a str,
+}
+
+impl Imported {
+    // Adds the ascent of a tick, or notes why there isn't one, where the
+    // label says which record the tick came from
+    fn add(&mut self, label: String, tick: Tick, options: &ImportOptions) {
+        let route = tick.route;
+
+        let style = match tick.style {
+            Ok(Style::Toprope) if options.leads_only => {
+                self.left_out += 1;
+                return;
+            }
+            Ok(style) => style,
+            Err(reason) => {
+                self.unmapped.push(format!("{label}: {route}: {reason}"));
+                return;
+            }
+        };
+
+        let Some((grade, scale)) = tick.mapped_grade else {
+            self.unmapped.push(format!(
+                "{label}: {route}: grade '{}' has no equivalent",
+                tick.grade,
+            ));
+            return;
+        };
+
+        // Logbooks often store a time along with the date
+        let date = tick.date.split(['T', ' ']).next();
+
+        let fields = [
+            Some(route),
+            Some(grade.as_str()),
+            Some(scale),
+            Some(tick.crag),
+            date,
+            Some(style.name()),
+            Some(tick.notes),
+        ];
+
+        match ascent_from_fields(fields) {
+            Ok(ascent) => self.ascents.push(ascent),
+            Err(e) => self.unmapped.push(format!("{label}: {route}: {e}")),
+        }
+    }
+}
+
+pub struct ImportSummary {
+    pub imported: Vec<Ascent>,
+    pub already_logged: usize,
+    pub left_out: usize,
+    pub unmapped: Vec<String>,
+}
+
+// Finds the index of every column of a CSV file, erroring if any required
+// ones are missing
+fn column_indices<const N: usize>(
+    headers: &csv::StringRecord,
+    columns: [&'static str; N],
+    required: &[&str],
+) -> Result<[Option<usize>; N]> {
+    let indices = columns.map(|column| {
+        headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(column))
+    });
+
+    for (column, index) in columns.iter().zip(indices) {
+        if index.is_none() && required.contains(column) {
+            return Err(Error::User(User::MissingColumn(column)));
+        }
+    }
+
+    Ok(indices)
+}
+
+const MOUNTAIN_PROJECT_COLUMNS: [&str; 7] = [
+    "Date",
+    "Route",
+    "Rating",
+    "Notes",
+    "Location",
+    "Style",
+    "Lead Style",
+];
+
+// Maps a Mountain Project rating, such as "5.10a/b PG13" or "V4-5", onto a
+// grade and scale, ignoring any protection or aid ratings after it
+fn mountain_project_grade(rating: &str) -> Option<(String, &'static str)> {
+    let rating = rating.split_whitespace().next()?;
+
+    if rating.starts_with("5.") {
+        let grade = rating.to_string();
+        return grades::find_scale("yds")?
+            .is_valid(&grade)
+            .then_some((grade, "yds"));
+    }
+
+    if let Some(number) = rating.strip_prefix('V') {
+        if number.starts_with("-easy") || number.starts_with('B') {
+            return Some(("VB".to_string(), "v"));
+        }
+
+        // Ranges and modifiers, as in V4-5 and V4+, go by the easier grade
+        let number: String = number.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let grade = format!("V{number}");
+
+        return grades::find_scale("v")?
+            .is_valid(&grade)
+            .then_some((grade, "v"));
+    }
+
+    None
+}
+
+// Maps a Mountain Project tick's style and lead style onto a style
+fn mountain_project_style(style: &str, lead_style: &str) -> result::Result<Style, String> {
+    match (
+        style.to_lowercase().as_str(),
+        lead_style.to_lowercase().as_str(),
+    ) {
+        ("tr" | "follow", _) => Ok(Style::Toprope),
+        ("lead" | "", "onsight") => Ok(Style::Onsight),
+        ("lead" | "", "flash") | ("flash", _) => Ok(Style::Flash),
+        ("lead" | "", "redpoint" | "") | ("send", _) => Ok(Style::Redpoint),
+        ("lead" | "", "pinkpoint") => Ok(Style::Pinkpoint),
+        (_, "fell/hung") | ("attempt", _) => Err("not a clean ascent".to_string()),
+        (style, _) => Err(format!("style '{style}' has no equivalent")),
+    }
+}
+
+/// Reads ticks from a Mountain Project tick export, where the crag is taken
+/// from the location path of each tick. Ticks that can't be mapped onto an
+/// ascent are reported rather than failing the whole import.
+pub fn read_mountain_project(reader: impl Read, options: &ImportOptions) -> Result<Imported> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| Error::User(User::InvalidImport(vec![format!("line 1: {e}")])))?
+        .clone();
+    let indices = column_indices(
+        &headers,
+        MOUNTAIN_PROJECT_COLUMNS,
+        &["Date", "Route", "Rating", "Location"],
+    )?;
+
+    let mut imported = Imported::default();
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                imported.unmapped.push(e.to_string());
+                continue;
+            }
+        };
+
+        let line = record.position().map_or(0, |position| position.line());
+        let [date, route, rating, notes, location, style, lead_style] = indices.map(|index| {
+            index
+                .and_then(|index| record.get(index))
+                .unwrap_or("")
+                .trim()
+        });
+
+        let parts: Vec<&str> = location.split('>').map(str::trim).collect();
+        let crag = match options.crag_level {
+            Some(level) => parts.get(level.saturating_sub(1)),
+            None => parts.get(parts.len().saturating_sub(2)),
+        };
+
+        let tick = Tick {
+            route,
+            grade: rating,
+            mapped_grade: mountain_project_grade(rating),
+            crag: crag.copied().unwrap_or(""),
+            date,
+            style: mountain_project_style(style, lead_style),
+            notes,
+        };
+
+        imported.add(format!("line {line}"), tick, options);
+    }
+
+    Ok(imported)
+}
+
+// Finds a known grade scale with the given grade, trying the preferred
+// scales and then every other scale of the discipline in turn, and ignoring
+// anything after the grade itself, such as the technical grade of "E1 5b"
+fn known_grade(
+    grade: &str,
+    discipline: Discipline,
+    preferred: &[&str],
+) -> Option<(String, &'static str)> {
+    let grade = grade.split_whitespace().next()?;
+
+    let preferred_scales = preferred.iter().filter_map(|name| grades::find_scale(name));
+
+    preferred_scales
+        .chain(grades::SCALES)
+        .filter(|scale| scale.discipline() == discipline)
+        .find(|scale| scale.is_valid(grade))
+        .map(|scale| (grade.to_string(), scale.name()))
+}
+
+const THE_CRAG_COLUMNS: [&str; 7] = [
+    "Ascent Date",
+    "Route Name",
+    "Route Grade",
+    "Ascent Grade",
+    "Route Gear Style",
+    "Crag Name",
+    "Ascent Type",
+];
+
+// Maps a theCrag ascent type, such as "Red point" or "Top rope clean", onto
+// a style
+fn the_crag_style(ascent_type: &str) -> result::Result<Style, String> {
+    match utils::normalize_name(ascent_type).as_str() {
+        "onsight" => Ok(Style::Onsight),
+        "flash" => Ok(Style::Flash),
+        "redpoint" | "groundupredpoint" | "send" => Ok(Style::Redpoint),
+        "pinkpoint" => Ok(Style::Pinkpoint),
+        "toprope" | "topropeclean" | "topropeonsight" | "topropeflash" | "second"
+        | "secondclean" => Ok(Style::Toprope),
+        "attempt" | "hangdog" | "dog" | "working" | "retreat" | "topropewithrest"
+        | "secondwithrest" => Err("not a clean ascent".to_string()),
+        _ => Err(format!("ascent type '{ascent_type}' has no equivalent")),
+    }
+}
+
+/// Reads ascents from a theCrag logbook export, using the grade given with
+/// each ascent if there is one, or else the grade of the route. Ascents that
+/// can't be mapped are reported rather than failing the whole import.
+pub fn read_the_crag(reader: impl Read, options: &ImportOptions) -> Result<Imported> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| Error::User(User::InvalidImport(vec![format!("line 1: {e}")])))?
+        .clone();
+    let indices = column_indices(
+        &headers,
+        THE_CRAG_COLUMNS,
+        &["Ascent Date", "Route Name", "Route Grade", "Crag Name"],
+    )?;
+
+    let mut imported = Imported::default();
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                imported.unmapped.push(e.to_string());
+                continue;
+            }
+        };
+
+        let line = record.position().map_or(0, |position| position.line());
+        let [date, route, route_grade, ascent_grade, gear_style, crag, ascent_type] =
+            indices.map(|index| {
+                index
+                    .and_then(|index| record.get(index))
+                    .unwrap_or("")
+                    .trim()
+            });
+
+        let grade = if ascent_grade.is_empty() {
+            route_grade
+        } else {
+            ascent_grade
+        };
+        let discipline = if gear_style.eq_ignore_ascii_case("boulder") {
+            Discipline::Boulder
+        } else {
+            Discipline::Route
+        };
+
+        let tick = Tick {
+            route,
+            grade,
+            // Bare numbers are far more likely to be Ewbank grades on theCrag
+            // than the easiest French grades
+            mapped_grade: known_grade(grade, discipline, &["ewbank"]),
+            crag,
+            date,
+            style: the_crag_style(ascent_type),
+            notes: "",
+        };
+
+        imported.add(format!("line {line}"), tick, options);
+    }
+
+    Ok(imported)
+}
+
+// One ascent of an 8a logbook export, where routes are graded in French
+// grades and boulders in Font grades
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EightAAscent {
+    date: String,
+    zlaggable_name: String,
+    crag_name: String,
+    difficulty: String,
+    #[serde(rename = "type")]
+    ascent_type: String,
+    #[serde(default)]
+    comment: Option<String>,
+    // 0 for routes and 1 for boulders
+    #[serde(default)]
+    category: u32,
+}
+
+// Maps an 8a ascent type, such as "os" for onsight, onto a style
+fn eight_a_style(ascent_type: &str) -> result::Result<Style, String> {
+    match ascent_type.to_lowercase().as_str() {
+        "os" => Ok(Style::Onsight),
+        "f" => Ok(Style::Flash),
+        "rp" => Ok(Style::Redpoint),
+        "tr" => Ok(Style::Toprope),
+        _ => Err(format!("ascent type '{ascent_type}' has no equivalent")),
+    }
+}
+
+/// Reads ascents from the JSON array of an 8a logbook export. Ascents that
+/// can't be mapped are reported rather than failing the whole import.
+pub fn read_eight_a(reader: impl Read, options: &ImportOptions) -> Result<Imported> {
+    let records: Vec<serde_json::Value> = serde_json::from_reader(reader)
+        .map_err(|e| Error::User(User::InvalidImport(vec![e.to_string()])))?;
+
+    let mut imported = Imported::default();
+
+    for (i, record) in records.into_iter().enumerate() {
+        let label = format!("ascent {}", i + 1);
+
+        let ascent: EightAAscent = match serde_json::from_value(record) {
+            Ok(ascent) => ascent,
+            Err(e) => {
+                imported.unmapped.push(format!("{label}: {e}"));
+                continue;
+            }
+        };
+
+        let discipline = match ascent.category {
+            1 => Discipline::Boulder,
+            _ => Discipline::Route,
+        };
+
+        let tick = Tick {
+            route: &ascent.zlaggable_name,
+            grade: &ascent.difficulty,
+            mapped_grade: known_grade(&ascent.difficulty, discipline, &[]),
+            crag: &ascent.crag_name,
+            date: &ascent.date,
+            style: eight_a_style(&ascent.ascent_type),
+            notes: ascent.comment.as_deref().unwrap_or(""),
+        };
+
+        imported.add(label, tick, options);
+    }
+
+    Ok(imported)
+}
+
+/// Imports every ascent in a file, skipping any already logged
+pub fn import_ascent_db(
+    database: &String,
+    file: &String,
+    format: Format,
+    options: &ImportOptions,
+) -> Result<ImportSummary> {
+    let db = AscentDB::new(database)?;
+
+    let reader = File::open(file).map_err(|_| Error::User(User::FileNotFound(file.clone())))?;
+
+    let imported = match format {
+        Format::Csv => Imported {
+            ascents: read_csv(reader)?,
+            ..Default::default()
+        },
+        Format::Json => Imported {
+            ascents: read_json(reader)?,
+            ..Default::default()
+        },
+        Format::Jsonl => Imported {
+            ascents: read_jsonl(reader)?,
+            ..Default::default()
+        },
+        Format::MountainProject => read_mountain_project(reader, options)?,
+        Format::TheCrag => read_the_crag(reader, options)?,
+        Format::EightA => read_eight_a(reader, options)?,
+    };
+
+    let logged = db.log_ascents(&imported.ascents, options.dry_run)?;
+
+    Ok(ImportSummary {
+        already_logged: imported.ascents.len() - logged.len(),
+        imported: logged.into_iter().cloned().collect(),
+        left_out: imported.left_out,
+        unmapped: imported.unmapped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn ascents() -> Vec<Ascent> {
+        vec![
+            Ascent::new(
+                Route::new(
+                    "Slither".to_string(),
+                    "5.7".to_string(),
+                    "yds".to_string(),
+                    "Reimers Ranch".to_string(),
+                )
+                .unwrap(),
+                date!(2022 - 06 - 27),
+                Style::Onsight,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Biographie, \"Realization\"".to_string(),
+                    "9a+".to_string(),
+                    "french".to_string(),
+                    "Céüse".to_string(),
+                )
+                .unwrap(),
+                date!(2023 - 07 - 01),
+                Style::Redpoint,
+            )
+            .with_notes(Some("Finally,\nafter many seasons".to_string())),
+        ]
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let csv = export::write_csv(&ascents());
+
+        assert!(csv.starts_with("route,grade,scale,crag,date,style,notes\n"));
+        assert_eq!(read_csv(csv.as_bytes()).unwrap(), ascents());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = export::write_json(&ascents());
+        assert_eq!(read_json(json.as_bytes()).unwrap(), ascents());
+
+        let jsonl = export::write_jsonl(&ascents());
+        assert_eq!(jsonl.lines().count(), 2);
+        assert_eq!(read_jsonl(jsonl.as_bytes()).unwrap(), ascents());
+    }
+
+    #[test]
+    fn json_shape() {
+        let json = serde_json::to_value(&ascents()[1]).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "route": {
+                    "name": "Biographie, \"Realization\"",
+                    "grade": "9a+",
+                    "scale": "french",
+                    "crag": "Céüse",
+                },
+                "date": "2023-07-01",
+                "style": "redpoint",
+                "notes": "Finally,\nafter many seasons",
+            }),
+        );
+    }
+
+    #[test]
+    fn json_errors() {
+        let jsonl = r#"
+            {"route": {"name": "Slither", "grade": "5.7", "crag": "Reimers Ranch"}, "date": "2022-06-27"}
+            {"route": {"name": "Slither", "grade": "5.17", "crag": "Reimers Ranch"}, "date": "2022-06-27"}
+            {"route": {"name": "Slither", "grade": "5.7", "crag": "Reimers Ranch"}, "date": "June"}
+        "#;
+
+        let Err(Error::User(User::InvalidImport(errors))) = read_jsonl(jsonl.as_bytes()) else {
+            panic!("expected an invalid import");
+        };
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("line 3: grade must be a valid YDS grade"));
+        assert!(errors[1].starts_with("line 4: "));
+
+        let json = r#"[{"route": {"name": "Slither", "grade": "5.7"}, "date": "2022-06-27"}]"#;
+
+        let Err(Error::User(User::InvalidImport(errors))) = read_json(json.as_bytes()) else {
+            panic!("expected an invalid import");
+        };
+
+        assert_eq!(errors, ["ascent 1: missing field `crag`"]);
+    }
+
+    #[test]
+    fn mountain_project() {
+        let csv = "\
+Date,Route,Rating,Notes,URL,Pitches,Location,\"Avg Stars\",\"Your Stars\",Style,\"Lead Style\",\"Route Type\",\"Your Rating\",Length,\"Rating Code\"
+2022-06-27,Slither,5.7,,https://www.mountainproject.com/route/1,1,\"Texas > Central Texas > Austin Area > Reimers Ranch > Dead Cats Wall\",2.5,-1,Lead,Onsight,Sport,,60,1200
+2022-06-28,\"Crack Attack\",\"5.10a/b PG13\",\"Bring doubles\",https://www.mountainproject.com/route/2,1,\"Texas > Central Texas > Austin Area > Reimers Ranch > Dead Cats Wall\",3,-1,TR,,Trad,,60,2600
+2022-07-01,Pebbles,V4-5,,https://www.mountainproject.com/route/3,1,\"Texas > Central Texas > Hueco > North Mountain\",3,-1,Send,,Boulder,,,20500
+2022-07-02,\"Ice Line\",WI4,,https://www.mountainproject.com/route/4,1,\"Colorado > Ouray > Ice Park\",3,-1,Lead,Redpoint,Ice,,,50000
+2022-07-03,Project,5.13a,,https://www.mountainproject.com/route/5,1,\"Texas > Central Texas > Austin Area > Reimers Ranch > Dead Cats Wall\",3,-1,Lead,Fell/Hung,Sport,,60,5500
+2022-07-04,Sloper,V2,,https://www.mountainproject.com/route/6,1,\"Texas > Central Texas > Hueco > North Mountain\",3,-1,Flash,,Boulder,,,20200
+";
+
+        let imported = read_mountain_project(csv.as_bytes(), &ImportOptions::default()).unwrap();
+
+        let ascents: Vec<String> = imported.ascents.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            ascents,
+            [
+                "Slither 5.7 (YDS) at Reimers Ranch on 2022-06-27 (onsight)",
+                "Crack Attack 5.10a/b (YDS) at Reimers Ranch on 2022-06-28 (toprope)",
+                "Pebbles V4 (V-scale) at Hueco on 2022-07-01 (redpoint)",
+                "Sloper V2 (V-scale) at Hueco on 2022-07-04 (flash)",
+            ],
+        );
+        assert_eq!(
+            imported.ascents[1].notes(),
+            Some(&"Bring doubles".to_string())
+        );
+        assert_eq!(
+            imported.unmapped,
+            [
+                "line 5: Ice Line: grade 'WI4' has no equivalent",
+                "line 6: Project: not a clean ascent",
+            ],
+        );
+
+        let options = ImportOptions {
+            leads_only: true,
+            crag_level: Some(5),
+            ..Default::default()
+        };
+        let imported = read_mountain_project(csv.as_bytes(), &options).unwrap();
+
+        assert_eq!(imported.left_out, 1);
+        assert_eq!(imported.ascents[0].route().crag(), "Dead Cats Wall");
+        assert_eq!(
+            imported.unmapped.last().unwrap(),
+            "line 7: Sloper: Must provide crag",
+        );
+    }
+
+    #[test]
+    fn the_crag() {
+        let csv = "\
+Ascent ID,Ascent Link,Ascent Type,Route ID,Route Link,Route Name,Route Grade,Ascent Grade,Route Gear Style,Route Height,Country,Crag Name,Crag Path,Ascent Date,Log Date,Comment
+1,https://x/1,Red point,11,https://x/11,Biographie,9a+,,Sport,,France,Céüse,France - Hautes-Alpes - Céüse,2001-07-01T00:00:00Z,2001-07-02T00:00:00Z,
+2,https://x/2,Top rope clean,12,https://x/12,Arapiles Classic,20,21,Trad,,Australia,Arapiles,Australia - Victoria - Arapiles,2019-03-10T00:00:00Z,2019-03-10T00:00:00Z,
+3,https://x/3,Flash,13,https://x/13,Problem,7A,,Boulder,,France,Fontainebleau,France - Fontainebleau,2020-05-01T00:00:00Z,2020-05-01T00:00:00Z,
+4,https://x/4,Hang dog,14,https://x/14,Hard Route,E5 6b,,Trad,,UK,Stanage,UK - Peak - Stanage,2021-06-01T00:00:00Z,2021-06-01T00:00:00Z,
+5,https://x/5,Onsight,15,https://x/15,Grit Classic,HVS 5a,,Trad,,UK,Stanage,UK - Peak - Stanage,2021-06-01T00:00:00Z,2021-06-01T00:00:00Z,
+6,https://x/6,Onsight,16,https://x/16,Ice Route,WI5,,Ice,,Canada,Field,Canada - Field,2022-01-01T00:00:00Z,2022-01-01T00:00:00Z,
+7,https://x/7,Tick,17,https://x/17,Some Slab,18,,Trad,,Australia,Arapiles,Australia - Victoria - Arapiles,2019-03-11T00:00:00Z,2019-03-11T00:00:00Z,
+8,https://x/8,,18,https://x/18,Other Slab,19,,Trad,,Australia,Arapiles,Australia - Victoria - Arapiles,2019-03-11T00:00:00Z,2019-03-11T00:00:00Z,
+9,https://x/9,Onsight,19,https://x/19,Easy Gully,3,,Trad,,Australia,Arapiles,Australia - Victoria - Arapiles,2019-03-12T00:00:00Z,2019-03-12T00:00:00Z,
+";
+
+        let imported = read_the_crag(csv.as_bytes(), &ImportOptions::default()).unwrap();
+
+        let ascents: Vec<String> = imported.ascents.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            ascents,
+            [
+                "Biographie 9a+ (French) at Céüse on 2001-07-01 (redpoint)",
+                "Arapiles Classic 21 (Ewbank) at Arapiles on 2019-03-10 (toprope)",
+                "Problem 7A (Font) at Fontainebleau on 2020-05-01 (flash)",
+                "Grit Classic HVS (British) at Stanage on 2021-06-01 (onsight)",
+                "Easy Gully 3 (Ewbank) at Arapiles on 2019-03-12 (onsight)",
+            ],
+        );
+        assert_eq!(
+            imported.unmapped,
+            [
+                "line 5: Hard Route: not a clean ascent",
+                "line 7: Ice Route: grade 'WI5' has no equivalent",
+                // A generic or blank tick doesn't say how the route was climbed
+                "line 8: Some Slab: ascent type 'Tick' has no equivalent",
+                "line 9: Other Slab: ascent type '' has no equivalent",
+            ],
+        );
+
+        let options = ImportOptions {
+            leads_only: true,
+            ..Default::default()
+        };
+        let imported = read_the_crag(csv.as_bytes(), &options).unwrap();
+
+        assert_eq!(imported.ascents.len(), 4);
+        assert_eq!(imported.left_out, 1);
+
+        // Bare numbers that are both French and Ewbank grades go by the
+        // preferred scale
+        assert_eq!(
+            known_grade("3", Discipline::Route, &[]),
+            Some(("3".to_string(), "french")),
+        );
+        assert_eq!(
+            known_grade("3", Discipline::Route, &["ewbank"]),
+            Some(("3".to_string(), "ewbank")),
+        );
+        assert_eq!(
+            known_grade("6a+", Discipline::Route, &["ewbank"]),
+            Some(("6a+".to_string(), "french")),
+        );
+    }
+
+    #[test]
+    fn eight_a() {
+        let json = r#"[
+            {
+                "date": "2001-07-01T00:00:00+00:00",
+                "zlaggableName": "Biographie",
+                "cragName": "Céüse",
+                "difficulty": "9a+",
+                "type": "rp",
+                "comment": "Finally",
+                "category": 0
+            },
+            {
+                "date": "2020-05-01T00:00:00+00:00",
+                "zlaggableName": "Problem",
+                "cragName": "Fontainebleau",
+                "difficulty": "7A",
+                "type": "f",
+                "category": 1
+            },
+            {
+                "date": "2020-05-02T00:00:00+00:00",
+                "zlaggableName": "Other Problem",
+                "cragName": "Fontainebleau",
+                "difficulty": "7A",
+                "type": "go",
+                "category": 1
+            },
+            {
+                "zlaggableName": "No Date"
+            }
+        ]"#;
+
+        let imported = read_eight_a(json.as_bytes(), &ImportOptions::default()).unwrap();
+
+        let ascents: Vec<String> = imported.ascents.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            ascents,
+            [
+                "Biographie 9a+ (French) at Céüse on 2001-07-01 (redpoint)",
+                "Problem 7A (Font) at Fontainebleau on 2020-05-01 (flash)",
+            ],
+        );
+        assert_eq!(imported.ascents[0].notes(), Some(&"Finally".to_string()));
+        assert_eq!(imported.unmapped.len(), 2);
+        assert_eq!(
+            imported.unmapped[0],
+            "ascent 3: Other Problem: ascent type 'go' has no equivalent",
+        );
+        assert!(imported.unmapped[1].starts_with("ascent 4: missing field `date`"));
+    }
+
+    #[test]
+    fn csv_defaults() {
+        let csv = "\
+            Date,Crag,Route,Grade\n\
+            2022-06-27,Reimers Ranch,Slither,5.7\n";
+
+        let ascent = &read_csv(csv.as_bytes()).unwrap()[0];
+
+        assert_eq!(
+            ascent.to_string(),
+            "Slither 5.7 (YDS) at Reimers Ranch on 2022-06-27 (redpoint)",
+        );
+        assert_eq!(ascent.notes(), None);
+    }
+
+    #[test]
+    fn csv_errors() {
+        let csv = "\
+            route,grade,scale,crag,date,style\n\
+            Slither,5.7,yds,Reimers Ranch,2022-06-27,onsight\n\
+            Slither,5.7,yds,Reimers Ranch,2022-13-01,onsight\n\
+            Slither,5.17,yds,Reimers Ranch,2022-06-27,onsight\n\
+            ,5.7,yds,Reimers Ranch,2022-06-27,onsight\n\
+            Slither,5.7,yds,Reimers Ranch,2022-06-27,hangdog\n";
+
+        assert_eq!(
+            read_csv(csv.as_bytes()).unwrap_err(),
+            Error::User(User::InvalidImport(vec![
+                "line 3: date must be a valid date in YYYY-MM-DD format".to_string(),
+                "line 4: grade must be a valid YDS grade".to_string(),
+                "line 5: Must provide route".to_string(),
+                format!("line 6: {}", User::InvalidStyle),
+            ])),
+        );
+
+        assert_eq!(
+            read_csv("route,grade,date\n".as_bytes()).unwrap_err(),
+            Error::User(User::MissingColumn("crag")),
+        );
+    }
+}
@@ -1,75 +1,198 @@
 use crate::{
     error::{Error, Result, User},
-    utils,
+    grades, migrate, utils,
 };
 use rusqlite::Connection;
 
-struct Grade {
-    value: String,
-    number: u32,
-    letter: Option<String>,
-}
-
-fn generate_grade_info_data() -> Vec<Grade> {
-    let mut grades = Vec::new();
-
-    for number in 0..=15 {
-        if number < 10 {
-            grades.push(Grade {
-                value: format!("5.{number}"),
-                number,
-                letter: None,
-            });
-        } else {
-            for letter in 'a'..='d' {
-                grades.push(Grade {
-                    value: format!("5.{number}{letter}"),
-                    number,
-                    letter: Some(letter.to_string()),
-                });
-            }
-        }
-    }
-
-    grades
-}
-
 pub fn init_ascent_db(database: &String) -> Result<()> {
     if utils::exists(database) {
         return Err(Error::User(User::DatabaseAlreadyExists));
     }
 
-    let grade_info_data = generate_grade_info_data();
-
-    let conn = Connection::open(database)?;
+    let mut conn = Connection::open(database)?;
+    let tx = conn.transaction()?;
 
-    conn.execute_batch(
+    tx.execute_batch(
         "
-        CREATE TABLE ascents(
-            route TEXT NOT NULL,
+        CREATE TABLE crags(
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        );
+
+        CREATE TABLE routes(
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
             grade TEXT NOT NULL,
-            crag TEXT NOT NULL,
+            scale TEXT NOT NULL,
+            crag_id INTEGER NOT NULL REFERENCES crags(id),
+            UNIQUE(name, grade, scale, crag_id),
+            FOREIGN KEY(grade, scale) REFERENCES grade_info(grade, scale)
+        );
+
+        CREATE TABLE ascents(
+            id INTEGER PRIMARY KEY,
+            route_id INTEGER NOT NULL REFERENCES routes(id),
             date TEXT NOT NULL,
-            PRIMARY KEY(route, grade, crag)
+            style TEXT NOT NULL DEFAULT 'redpoint',
+            notes TEXT,
+            UNIQUE(route_id, date)
         );
+        ",
+    )?;
+
+    create_grade_tables(&tx)?;
+    create_search_index(&tx)?;
+    migrate::set_schema_version(&tx, migrate::SCHEMA_VERSION)?;
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Creates the grade_info and grade_conversions tables from the grade
+/// scale registry, replacing any existing ones. These tables only hold
+/// reference data, so they can be rebuilt at any time.
+pub fn create_grade_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        DROP TABLE IF EXISTS grade_info;
+        DROP TABLE IF EXISTS grade_conversions;
 
         CREATE TABLE grade_info(
-            grade TEXT PRIMARY KEY,
+            grade TEXT NOT NULL,
+            scale TEXT NOT NULL,
             grade_number INTEGER NOT NULL,
-            grade_letter TEXT
+            grade_letter TEXT,
+            grade_order INTEGER NOT NULL,
+            difficulty REAL NOT NULL,
+            PRIMARY KEY(grade, scale)
+        );
+
+        CREATE TABLE grade_conversions(
+            grade TEXT NOT NULL,
+            scale TEXT NOT NULL,
+            to_grade TEXT NOT NULL,
+            to_scale TEXT NOT NULL,
+            PRIMARY KEY(grade, scale, to_scale)
         );
         ",
     )?;
 
-    for grade in grade_info_data {
-        conn.execute(
-            "
-            INSERT INTO grade_info
-            VALUES(?, ?, ?)
-            ",
-            (grade.value, grade.number, grade.letter),
-        )?;
+    for scale in grades::SCALES {
+        for (order, grade) in scale.grades().iter().enumerate() {
+            conn.execute(
+                "
+                INSERT INTO grade_info
+                VALUES(?, ?, ?, ?, ?, ?)
+                ",
+                (
+                    grade.value(),
+                    scale.name(),
+                    grade.number(),
+                    grade.letter(),
+                    order,
+                    grade.difficulty(),
+                ),
+            )?;
+
+            for to_scale in grades::SCALES {
+                if let Some(to_grade) = grades::convert(grade.value(), scale, to_scale) {
+                    conn.execute(
+                        "
+                        INSERT INTO grade_conversions
+                        VALUES(?, ?, ?, ?)
+                        ",
+                        (grade.value(), scale.name(), to_grade, to_scale.name()),
+                    )?;
+                }
+            }
+        }
     }
 
     Ok(())
 }
+
+/// Creates the full-text search index over the route, crag and notes of
+/// every ascent, along with the triggers that keep it up to date, replacing
+/// any existing ones. Like the grade tables, it can be rebuilt at any time.
+pub fn create_search_index(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        DROP TABLE IF EXISTS ascent_search;
+        DROP TRIGGER IF EXISTS ascents_search_insert;
+        DROP TRIGGER IF EXISTS ascents_search_delete;
+        DROP TRIGGER IF EXISTS ascents_search_update;
+        DROP TRIGGER IF EXISTS routes_search_update;
+        DROP TRIGGER IF EXISTS crags_search_update;
+
+        CREATE VIRTUAL TABLE ascent_search USING fts5(
+            route,
+            crag,
+            notes,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        INSERT INTO ascent_search(rowid, route, crag, notes)
+        SELECT ascents.id, routes.name, crags.name, ascents.notes
+        FROM ascents
+        JOIN routes ON routes.id = ascents.route_id
+        JOIN crags ON crags.id = routes.crag_id;
+
+        CREATE TRIGGER ascents_search_insert AFTER INSERT ON ascents
+        BEGIN
+            INSERT INTO ascent_search(rowid, route, crag, notes)
+            SELECT new.id, routes.name, crags.name, new.notes
+            FROM routes
+            JOIN crags ON crags.id = routes.crag_id
+            WHERE routes.id = new.route_id;
+        END;
+
+        CREATE TRIGGER ascents_search_delete AFTER DELETE ON ascents
+        BEGIN
+            DELETE FROM ascent_search WHERE rowid = old.id;
+        END;
+
+        CREATE TRIGGER ascents_search_update AFTER UPDATE ON ascents
+        BEGIN
+            DELETE FROM ascent_search WHERE rowid = old.id;
+
+            INSERT INTO ascent_search(rowid, route, crag, notes)
+            SELECT new.id, routes.name, crags.name, new.notes
+            FROM routes
+            JOIN crags ON crags.id = routes.crag_id
+            WHERE routes.id = new.route_id;
+        END;
+
+        CREATE TRIGGER routes_search_update AFTER UPDATE ON routes
+        BEGIN
+            DELETE FROM ascent_search
+            WHERE rowid IN (SELECT id FROM ascents WHERE route_id = new.id);
+
+            INSERT INTO ascent_search(rowid, route, crag, notes)
+            SELECT ascents.id, new.name, crags.name, ascents.notes
+            FROM ascents
+            JOIN crags ON crags.id = new.crag_id
+            WHERE ascents.route_id = new.id;
+        END;
+
+        CREATE TRIGGER crags_search_update AFTER UPDATE ON crags
+        BEGIN
+            DELETE FROM ascent_search
+            WHERE rowid IN (
+                SELECT ascents.id
+                FROM ascents
+                JOIN routes ON routes.id = ascents.route_id
+                WHERE routes.crag_id = new.id
+            );
+
+            INSERT INTO ascent_search(rowid, route, crag, notes)
+            SELECT ascents.id, routes.name, new.name, ascents.notes
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            WHERE routes.crag_id = new.id;
+        END;
+        ",
+    )?;
+
+    Ok(())
+}
@@ -2,6 +2,11 @@ pub mod cli;
 pub mod error;
 
 mod analyze;
+mod export;
+mod grades;
+mod import;
 mod init;
+mod list;
+mod migrate;
 mod models;
 mod utils;
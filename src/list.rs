@@ -0,0 +1,91 @@
+use crate::{
+    error::Result,
+    grades,
+    models::{Ascent, AscentDB, AscentFilter, Sort},
+};
+
+const HEADERS: [&str; 7] = ["Date", "Route", "Grade", "Scale", "Crag", "Style", "Notes"];
+
+pub fn make_ascents_table(ascents: &[Ascent]) -> String {
+    let rows: Vec<[String; 7]> = ascents
+        .iter()
+        .map(|ascent| {
+            let route = ascent.route();
+            let scale =
+                grades::find_scale(route.scale()).expect("Scale should have been validated");
+
+            [
+                ascent.date().to_string(),
+                route.name().clone(),
+                route.grade().clone(),
+                scale.label().to_string(),
+                route.crag().clone(),
+                ascent.style().to_string(),
+                ascent.notes().cloned().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADERS.map(|header| header.len());
+
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let make_line = |cells: [&str; 7]| -> String {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+
+        line.join("  ").trim_end().to_string()
+    };
+
+    let mut table = vec![make_line(HEADERS)];
+
+    for row in &rows {
+        table.push(make_line(row.each_ref().map(|cell| cell.as_str())));
+    }
+
+    table.join("\n")
+}
+
+fn make_ascents_report(ascents: &[Ascent]) -> String {
+    if ascents.is_empty() {
+        return "No ascents found".to_string();
+    }
+
+    let count = match ascents.len() {
+        1 => "1 ascent".to_string(),
+        n => format!("{n} ascents"),
+    };
+
+    format!("{}\n\n{count}", make_ascents_table(ascents))
+}
+
+pub fn list_ascent_db(
+    database: &String,
+    filter: &AscentFilter,
+    sort: Sort,
+    reverse: bool,
+) -> Result<String> {
+    let db = AscentDB::new(database)?;
+
+    let mut ascents = db.list_ascents(filter, sort)?;
+
+    if reverse {
+        ascents.reverse();
+    }
+
+    Ok(make_ascents_report(&ascents))
+}
+
+pub fn search_ascent_db(database: &String, text: &str) -> Result<String> {
+    let db = AscentDB::new(database)?;
+    let ascents = db.search_ascents(text)?;
+
+    Ok(make_ascents_report(&ascents))
+}
@@ -8,7 +8,7 @@ fn main() {
     let args = match Args::new(env::args()) {
         Ok(args) => args,
         Err(e) => {
-            eprintln!("{}\nError: {e}", cli::USAGE);
+            eprintln!("Error: {e}\n\n{}", cli::USAGE);
             process::exit(1);
         }
     };
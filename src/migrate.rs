@@ -0,0 +1,285 @@
+use crate::{
+    error::{Error, Result, User},
+    init, utils,
+};
+use rusqlite::Connection;
+
+type Migration = fn(&Connection) -> Result<()>;
+
+// Migrations in the order they are applied. A database at schema version N
+// has had the first N migrations applied, with version 0 being the
+// original ascents table. Never edit a migration that has been released,
+// only add new ones.
+const MIGRATIONS: [Migration; 3] = [add_scale_and_style, add_routes_and_crags, add_notes];
+
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    let version = conn.query_row("PRAGMA user_version", (), |row| row.get(0))?;
+    Ok(version)
+}
+
+pub fn set_schema_version(conn: &Connection, version: u32) -> Result<()> {
+    conn.execute_batch(&format!("PRAGMA user_version = {version}"))?;
+    Ok(())
+}
+
+/// Errors unless the database is at the schema version of this binary
+pub fn check_schema_version(conn: &Connection) -> Result<()> {
+    let version = schema_version(conn)?;
+
+    if version > SCHEMA_VERSION {
+        return Err(Error::User(User::DatabaseTooNew(version)));
+    }
+
+    if version < SCHEMA_VERSION {
+        return Err(Error::User(User::DatabaseNeedsMigration));
+    }
+
+    Ok(())
+}
+
+/// Applies any pending migrations, returning the schema version the
+/// database was at beforehand
+fn run_migrations(conn: &mut Connection) -> Result<u32> {
+    let version = schema_version(conn)?;
+
+    if version > SCHEMA_VERSION {
+        return Err(Error::User(User::DatabaseTooNew(version)));
+    }
+
+    let tx = conn.transaction()?;
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&tx)?;
+    }
+
+    // Pick up any new or corrected grades, and any changes to the search
+    // index, along the way
+    init::create_grade_tables(&tx)?;
+    init::create_search_index(&tx)?;
+    set_schema_version(&tx, SCHEMA_VERSION)?;
+
+    tx.commit()?;
+
+    Ok(version)
+}
+
+pub fn migrate_ascent_db(database: &String) -> Result<u32> {
+    if !utils::exists(database) {
+        return Err(Error::User(User::DatabaseNotFound));
+    }
+
+    let mut conn = Connection::open(database)?;
+
+    run_migrations(&mut conn)
+}
+
+// Grade scales were added with every existing ascent graded in YDS, and
+// styles with every existing ascent being a redpoint
+fn add_scale_and_style(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE ascents ADD COLUMN scale TEXT NOT NULL DEFAULT 'yds';
+        ALTER TABLE ascents ADD COLUMN style TEXT NOT NULL DEFAULT 'redpoint';
+        ",
+    )?;
+
+    Ok(())
+}
+
+// Routes and crags moved to their own tables, with ascents referring to
+// routes by id so that a route can be sent more than once
+fn add_routes_and_crags(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE crags(
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        );
+
+        CREATE TABLE routes(
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            grade TEXT NOT NULL,
+            scale TEXT NOT NULL,
+            crag_id INTEGER NOT NULL REFERENCES crags(id),
+            UNIQUE(name, grade, scale, crag_id),
+            FOREIGN KEY(grade, scale) REFERENCES grade_info(grade, scale)
+        );
+
+        INSERT INTO crags(name)
+        SELECT DISTINCT crag
+        FROM ascents
+        ORDER BY crag;
+
+        INSERT INTO routes(name, grade, scale, crag_id)
+        SELECT DISTINCT route, grade, scale, crags.id
+        FROM ascents
+        JOIN crags ON crags.name = ascents.crag;
+
+        ALTER TABLE ascents RENAME TO old_ascents;
+
+        CREATE TABLE ascents(
+            id INTEGER PRIMARY KEY,
+            route_id INTEGER NOT NULL REFERENCES routes(id),
+            date TEXT NOT NULL,
+            style TEXT NOT NULL DEFAULT 'redpoint',
+            UNIQUE(route_id, date)
+        );
+
+        INSERT INTO ascents(route_id, date, style)
+        SELECT routes.id, old_ascents.date, old_ascents.style
+        FROM old_ascents
+        JOIN crags ON crags.name = old_ascents.crag
+        JOIN routes
+            ON routes.name = old_ascents.route
+            AND routes.grade = old_ascents.grade
+            AND routes.scale = old_ascents.scale
+            AND routes.crag_id = crags.id
+        ORDER BY old_ascents.date;
+
+        DROP TABLE old_ascents;
+        ",
+    )?;
+
+    Ok(())
+}
+
+// Notes were added as an optional free text field on each ascent
+fn add_notes(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE ascents ADD COLUMN notes TEXT")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Schema created by the original version of init
+    fn set_up_original_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute_batch(
+            "
+            CREATE TABLE ascents(
+                route TEXT NOT NULL,
+                grade TEXT NOT NULL,
+                crag TEXT NOT NULL,
+                date TEXT NOT NULL,
+                PRIMARY KEY(route, grade, crag)
+            );
+
+            CREATE TABLE grade_info(
+                grade TEXT PRIMARY KEY,
+                grade_number INTEGER NOT NULL,
+                grade_letter TEXT
+            );
+
+            INSERT INTO ascents
+            VALUES
+                ('Slither', '5.7', 'Reimers Ranch', '2022-06-27'),
+                ('Some Route', '5.10a', 'Reimers Ranch', '2023-01-01'),
+                ('Other Route', '5.11c', 'Other Crag', '2022-01-01');
+            ",
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn migrate_original_db() {
+        let mut conn = set_up_original_db();
+
+        assert_eq!(run_migrations(&mut conn).unwrap(), 0);
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(check_schema_version(&conn).is_ok());
+
+        let mut statement = conn
+            .prepare(
+                "
+                SELECT routes.name, grade, scale, crags.name, date, style
+                FROM ascents
+                JOIN routes ON routes.id = ascents.route_id
+                JOIN crags ON crags.id = routes.crag_id
+                ORDER BY date
+                ",
+            )
+            .unwrap();
+
+        let rows: Vec<(String, String, String, String, String, String)> = statement
+            .query_map((), |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+
+        let expected: Vec<(String, String, String, String, String, String)> = [
+            ("Other Route", "5.11c", "Other Crag", "2022-01-01"),
+            ("Slither", "5.7", "Reimers Ranch", "2022-06-27"),
+            ("Some Route", "5.10a", "Reimers Ranch", "2023-01-01"),
+        ]
+        .into_iter()
+        .map(|(route, grade, crag, date)| {
+            (
+                route.to_string(),
+                grade.to_string(),
+                "yds".to_string(),
+                crag.to_string(),
+                date.to_string(),
+                "redpoint".to_string(),
+            )
+        })
+        .collect();
+
+        assert_eq!(rows, expected);
+
+        let indexed: u32 = conn
+            .query_row(
+                "SELECT count(*) FROM ascent_search WHERE ascent_search MATCH 'reimers'",
+                (),
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(indexed, 2);
+    }
+
+    #[test]
+    fn migrate_up_to_date_db() {
+        let mut conn = set_up_original_db();
+
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(run_migrations(&mut conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn database_too_new() {
+        let mut conn = set_up_original_db();
+        set_schema_version(&conn, SCHEMA_VERSION + 1).unwrap();
+
+        let expected = Error::User(User::DatabaseTooNew(SCHEMA_VERSION + 1));
+
+        assert_eq!(check_schema_version(&conn).unwrap_err(), expected);
+        assert_eq!(run_migrations(&mut conn).unwrap_err(), expected);
+    }
+
+    #[test]
+    fn database_needs_migration() {
+        let conn = set_up_original_db();
+
+        assert_eq!(
+            check_schema_version(&conn).unwrap_err(),
+            Error::User(User::DatabaseNeedsMigration),
+        );
+    }
+}
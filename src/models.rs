@@ -1,28 +1,53 @@
 use crate::{
     error::{Error, Result, User},
-    utils,
+    grades, migrate, utils,
 };
-use regex::Regex;
-use rusqlite::Connection;
+use rusqlite::{params_from_iter, Connection, OptionalExtension, Params};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use time::Date;
 
-#[derive(Debug, PartialEq)]
+// Dates are serialized in the same YYYY-MM-DD format they are stored in
+time::serde::format_description!(date_format, Date, "[year]-[month]-[day]");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RouteFields")]
 pub struct Route {
     name: String,
     grade: String,
+    scale: String,
     crag: String,
 }
 
 impl Route {
-    pub fn new(name: String, grade: String, crag: String) -> Result<Self> {
-        let valid_yds = Regex::new(r"^5\.([0-9]|1[0-5][a-d])$").expect("Regex should compile");
-
-        if !valid_yds.is_match(&grade) {
-            return Err(Error::User(User::InvalidGrade));
+    pub fn new(name: String, grade: String, scale: String, crag: String) -> Result<Self> {
+        let grade_scale = match grades::find_scale(&scale) {
+            Some(grade_scale) => grade_scale,
+            None => return Err(Error::User(User::InvalidScale)),
+        };
+
+        if !grade_scale.is_valid(&grade) {
+            return Err(Error::User(User::InvalidGrade(grade_scale.label())));
         }
 
-        Ok(Self { name, grade, crag })
+        Ok(Self {
+            name,
+            grade,
+            scale,
+            crag,
+        })
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn grade(&self) -> &String {
+        &self.grade
+    }
+
+    pub fn scale(&self) -> &String {
+        &self.scale
     }
 
     pub fn crag(&self) -> &String {
@@ -30,41 +55,455 @@ impl Route {
     }
 }
 
+// Fields of a deserialized route, which are only a route once validated
+#[derive(Deserialize)]
+struct RouteFields {
+    name: String,
+    grade: String,
+    #[serde(default = "default_scale")]
+    scale: String,
+    crag: String,
+}
+
+fn default_scale() -> String {
+    grades::DEFAULT_SCALE.to_string()
+}
+
+impl TryFrom<RouteFields> for Route {
+    type Error = Error;
+
+    fn try_from(fields: RouteFields) -> Result<Self> {
+        Route::new(fields.name, fields.grade, fields.scale, fields.crag)
+    }
+}
+
 impl fmt::Display for Route {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {} at {}", self.name, self.grade, self.crag)
+        let scale = grades::find_scale(&self.scale).expect("Scale should have been validated");
+
+        write!(
+            f,
+            "{} {} ({}) at {}",
+            self.name,
+            self.grade,
+            scale.label(),
+            self.crag,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    Onsight,
+    Flash,
+    Redpoint,
+    Pinkpoint,
+    Toprope,
+}
+
+impl Style {
+    // Every style, from most to least impressive
+    pub const ALL: [Style; 5] = [
+        Style::Onsight,
+        Style::Flash,
+        Style::Redpoint,
+        Style::Pinkpoint,
+        Style::Toprope,
+    ];
+
+    pub const DEFAULT: Style = Style::Redpoint;
+
+    pub fn new(style: &str) -> Result<Self> {
+        match Style::ALL.into_iter().find(|s| s.name() == style) {
+            Some(style) => Ok(style),
+            None => Err(Error::User(User::InvalidStyle)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Style::Onsight => "onsight",
+            Style::Flash => "flash",
+            Style::Redpoint => "redpoint",
+            Style::Pinkpoint => "pinkpoint",
+            Style::Toprope => "toprope",
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        Style::ALL.iter().map(|style| style.name()).collect()
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style::DEFAULT
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Ascent {
     route: Route,
+    #[serde(with = "date_format")]
     date: Date,
+    #[serde(default)]
+    style: Style,
+    #[serde(default)]
+    notes: Option<String>,
 }
 
 impl Ascent {
-    pub fn new(route: Route, date: Date) -> Self {
-        Self { route, date }
+    pub fn new(route: Route, date: Date, style: Style) -> Self {
+        Self {
+            route,
+            date,
+            style,
+            notes: None,
+        }
+    }
+
+    pub fn with_notes(mut self, notes: Option<String>) -> Self {
+        self.notes = notes;
+        self
     }
 
     pub fn route(&self) -> &Route {
         &self.route
     }
+
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    pub fn notes(&self) -> Option<&String> {
+        self.notes.as_ref()
+    }
 }
 
 impl fmt::Display for Ascent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} on {}", self.route, self.date)
+        write!(f, "{} on {} ({})", self.route, self.date, self.style)
+    }
+}
+
+/// Criteria for picking out ascents, where an ascent has to meet every
+/// criterion that is set
+#[derive(Debug, Clone, Default)]
+pub struct AscentFilter {
+    pub crag: Option<String>,
+    // Matches any route whose name contains this, ignoring case
+    pub route: Option<String>,
+    pub since: Option<Date>,
+    pub until: Option<Date>,
+    pub year: Option<i32>,
+    pub grade_min: Option<String>,
+    pub grade_max: Option<String>,
+    // Scale of the grade bounds, with routes in other scales of the same
+    // discipline converted to it for comparison
+    pub scale: Option<String>,
+}
+
+// Ids of the ascents that meet a filter, given the parameters of the filter
+// as ?1 to ?8, so that listing and counting ascents agree on which match.
+// Grade bounds compare grades converted to the scale of the filter.
+const FILTERED_ASCENT_IDS: &str = "
+    SELECT ascents.id
+    FROM ascents
+    JOIN routes ON routes.id = ascents.route_id
+    JOIN crags ON crags.id = routes.crag_id
+    LEFT JOIN grade_conversions AS converted
+        ON converted.grade = routes.grade
+        AND converted.scale = routes.scale
+        AND converted.to_scale = ?1
+    LEFT JOIN grade_info AS converted_info
+        ON converted_info.grade = converted.to_grade
+        AND converted_info.scale = converted.to_scale
+    WHERE (?2 IS NULL OR crags.name = ?2 COLLATE NOCASE)
+        AND (?3 IS NULL OR instr(lower(routes.name), lower(?3)) > 0)
+        AND (?4 IS NULL OR date >= ?4)
+        AND (?5 IS NULL OR date <= ?5)
+        AND (?6 IS NULL OR strftime('%Y', date) = ?6)
+        AND (?7 IS NULL OR converted_info.grade_order >= (
+            SELECT grade_order FROM grade_info WHERE grade = ?7 AND scale = ?1
+        ))
+        AND (?8 IS NULL OR converted_info.grade_order <= (
+            SELECT grade_order FROM grade_info WHERE grade = ?8 AND scale = ?1
+        ))
+";
+
+impl AscentFilter {
+    // Parameters of FILTERED_ASCENT_IDS, followed by any others the query
+    // needs from ?9 on
+    fn params(&self, others: &[Option<&str>]) -> Result<Vec<Option<String>>> {
+        let mut params = vec![
+            Some(self.scale()?.name().to_string()),
+            self.crag.clone(),
+            self.route.clone(),
+            self.since.map(format_date),
+            self.until.map(format_date),
+            self.year.map(|year| format!("{year:04}")),
+            self.grade_min.clone(),
+            self.grade_max.clone(),
+        ];
+
+        params.extend(others.iter().map(|other| other.map(String::from)));
+
+        Ok(params)
+    }
+
+    fn scale(&self) -> Result<&'static dyn grades::GradeScale> {
+        let name = self.scale.as_deref().unwrap_or(grades::DEFAULT_SCALE);
+        let scale = grades::find_scale(name).ok_or(Error::User(User::InvalidScale))?;
+
+        for grade in [&self.grade_min, &self.grade_max].into_iter().flatten() {
+            if !scale.is_valid(grade) {
+                return Err(Error::User(User::InvalidGrade(scale.label())));
+            }
+        }
+
+        Ok(scale)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sort {
+    Date,
+    Grade,
+    Crag,
+}
+
+impl Sort {
+    pub const ALL: [Sort; 3] = [Sort::Date, Sort::Grade, Sort::Crag];
+
+    pub fn new(sort: &str) -> Result<Self> {
+        match Sort::ALL.into_iter().find(|s| s.name() == sort) {
+            Some(sort) => Ok(sort),
+            None => Err(Error::User(User::InvalidSort)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sort::Date => "date",
+            Sort::Grade => "grade",
+            Sort::Crag => "crag",
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        Sort::ALL.iter().map(|sort| sort.name()).collect()
+    }
+
+    fn order_by(&self) -> String {
+        match self {
+            Sort::Date => "date, routes.name".to_string(),
+            // Difficulties only compare within a discipline, so every route
+            // comes before any boulder
+            Sort::Grade => {
+                let disciplines: Vec<String> = grades::SCALES
+                    .iter()
+                    .map(|scale| {
+                        format!("WHEN '{}' THEN {}", scale.name(), scale.discipline() as u8)
+                    })
+                    .collect();
+
+                format!(
+                    "CASE grade_info.scale {} END, \
+                    grade_info.difficulty, grade_info.grade_order, date",
+                    disciplines.join(" "),
+                )
+            }
+            Sort::Crag => "crags.name, date".to_string(),
+        }
+    }
+}
+
+/// Periods of time ascents can be grouped by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Year,
+    Month,
+}
+
+impl Period {
+    pub const ALL: [Period; 2] = [Period::Year, Period::Month];
+
+    pub const DEFAULT: Period = Period::Year;
+
+    pub fn new(period: &str) -> Result<Self> {
+        match Period::ALL.into_iter().find(|p| p.name() == period) {
+            Some(period) => Ok(period),
+            None => Err(Error::User(User::InvalidPeriod)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Period::Year => "year",
+            Period::Month => "month",
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        Period::ALL.iter().map(|period| period.name()).collect()
+    }
+
+    // Format of the period of a date, for strftime
+    fn format(&self) -> &'static str {
+        match self {
+            Period::Year => "%Y",
+            Period::Month => "%Y-%m",
+        }
+    }
+}
+
+impl Default for Period {
+    fn default() -> Self {
+        Period::DEFAULT
+    }
+}
+
+/// What the rows of a cross-tabulation of ascents by year are
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Crosstab {
+    Grade,
+    Crag,
+}
+
+impl Crosstab {
+    pub const ALL: [Crosstab; 2] = [Crosstab::Grade, Crosstab::Crag];
+
+    pub fn new(crosstab: &str) -> Result<Self> {
+        match Crosstab::ALL.into_iter().find(|c| c.name() == crosstab) {
+            Some(crosstab) => Ok(crosstab),
+            None => Err(Error::User(User::InvalidCrosstab)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Crosstab::Grade => "grade",
+            Crosstab::Crag => "crag",
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        Crosstab::ALL
+            .iter()
+            .map(|crosstab| crosstab.name())
+            .collect()
+    }
+}
+
+/// File formats ascents can be exported to and imported from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    // JSON Lines, also known as NDJSON, with one ascent per line
+    Jsonl,
+    // Logbook exports of other sites, which can only be imported
+    MountainProject,
+    TheCrag,
+    EightA,
+}
+
+impl Format {
+    pub const ALL: [Format; 6] = [
+        Format::Csv,
+        Format::Json,
+        Format::Jsonl,
+        Format::MountainProject,
+        Format::TheCrag,
+        Format::EightA,
+    ];
+
+    pub fn new(format: &str) -> Result<Self> {
+        match Format::ALL.into_iter().find(|f| f.name() == format) {
+            Some(format) => Ok(format),
+            None => Err(Error::User(User::InvalidFormat)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Jsonl => "jsonl",
+            Format::MountainProject => "mountainproject",
+            Format::TheCrag => "thecrag",
+            Format::EightA => "8a",
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        Format::ALL.iter().map(|format| format.name()).collect()
+    }
+}
+
+/// Formats the analysis of ascents can be reported in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 4] = [
+        ReportFormat::Text,
+        ReportFormat::Json,
+        ReportFormat::Csv,
+        ReportFormat::Markdown,
+    ];
+
+    pub fn new(format: &str) -> Result<Self> {
+        match ReportFormat::ALL.into_iter().find(|f| f.name() == format) {
+            Some(format) => Ok(format),
+            None => Err(Error::User(User::InvalidReportFormat)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReportFormat::Text => "text",
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Markdown => "markdown",
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        ReportFormat::ALL
+            .iter()
+            .map(|format| format.name())
+            .collect()
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
 pub struct Count {
     category: String,
     value: u32,
 }
 
 impl Count {
+    pub fn new(category: String, value: u32) -> Self {
+        Self { category, value }
+    }
+
     pub fn category(&self) -> &String {
         &self.category
     }
@@ -85,6 +524,8 @@ impl AscentDB {
         }
 
         let connection = Connection::open(database)?;
+        migrate::check_schema_version(&connection)?;
+        connection.execute_batch("PRAGMA foreign_keys = ON")?;
 
         Ok(Self { connection })
     }
@@ -94,9 +535,9 @@ impl AscentDB {
 
         let mut statement = self.connection.prepare(
             "
-            SELECT DISTINCT crag
-            FROM ascents
-            ORDER BY crag
+            SELECT name
+            FROM crags
+            ORDER BY name
             ",
         )?;
 
@@ -109,185 +550,964 @@ impl AscentDB {
         Ok(crags)
     }
 
-    pub fn log_ascent(&self, ascent: &Ascent) -> Result<()> {
-        let mut statement = self.connection.prepare(
+    /// Renames a crag, returning the number of ascents at it
+    pub fn rename_crag(&self, old: &str, new: &str) -> Result<usize> {
+        let crag_id = match find_crag_id(&self.connection, old)? {
+            Some(crag_id) => crag_id,
+            None => return Err(Error::User(User::CragNotFound(old.to_string()))),
+        };
+
+        if find_crag_id(&self.connection, new)?.is_some() {
+            return Err(Error::User(User::CragAlreadyExists(new.to_string())));
+        }
+
+        let tx = self.connection.unchecked_transaction()?;
+
+        tx.execute(
             "
-            SELECT date
-            FROM ascents
-            WHERE route = ? AND grade = ? AND crag = ?
+            UPDATE crags
+            SET name = ?
+            WHERE id = ?
             ",
+            (new, crag_id),
         )?;
 
-        let mut rows = statement.query_map(
-            (&ascent.route.name, &ascent.route.grade, &ascent.route.crag),
-            |row| row.get::<usize, String>(0),
-        )?;
+        let count = crag_ascent_count(&tx, crag_id)?;
+
+        tx.commit()?;
+
+        Ok(count)
+    }
 
-        if let Some(date) = rows.next() {
-            return Err(Error::User(User::AscentAlreadyLogged(date?)));
+    /// Moves every ascent at one crag to another, removing the first crag,
+    /// and returns the number of ascents moved. Routes at both crags with the
+    /// same name and grade are merged into one.
+    pub fn merge_crags(&self, from: &str, into: &str) -> Result<usize> {
+        // Everything is checked and counted in the same transaction as the
+        // merge itself, so nothing can change in between
+        let tx = self.connection.unchecked_transaction()?;
+
+        let (from_id, into_id) = match (find_crag_id(&tx, from)?, find_crag_id(&tx, into)?) {
+            (Some(from_id), Some(into_id)) => (from_id, into_id),
+            (None, _) => return Err(Error::User(User::CragNotFound(from.to_string()))),
+            (_, None) => return Err(Error::User(User::CragNotFound(into.to_string()))),
+        };
+
+        if from_id == into_id {
+            return Err(Error::User(User::SameCrag));
         }
 
-        self.connection.execute(
+        // Merging routes is only possible when that wouldn't leave the same
+        // route sent twice on one day
+        let mut statement = tx.prepare(
             "
-            INSERT INTO ascents(route, grade, crag, date)
-            VALUES(?, ?, ?, ?)
+            SELECT from_routes.name, from_routes.grade, from_routes.scale, ?3,
+                from_ascents.date, from_ascents.style, from_ascents.notes
+            FROM routes AS from_routes
+            JOIN ascents AS from_ascents ON from_ascents.route_id = from_routes.id
+            JOIN routes AS into_routes
+                ON into_routes.name = from_routes.name
+                AND into_routes.grade = from_routes.grade
+                AND into_routes.scale = from_routes.scale
+            JOIN ascents AS into_ascents
+                ON into_ascents.route_id = into_routes.id
+                AND into_ascents.date = from_ascents.date
+            WHERE from_routes.crag_id = ?1 AND into_routes.crag_id = ?2
+            ORDER BY from_ascents.date
             ",
-            (
-                &ascent.route.name,
-                &ascent.route.grade,
-                &ascent.route.crag,
-                format_date(ascent.date),
-            ),
         )?;
 
-        Ok(())
-    }
+        let collisions: Vec<String> = statement
+            .query_map((from_id, into_id, into), ascent_from_row)?
+            .map(|ascent| ascent.map(|ascent| ascent.to_string()))
+            .collect::<rusqlite::Result<_>>()?;
 
-    pub fn find_ascent(&self, route: Route) -> Result<Ascent> {
-        let mut statement = self.connection.prepare(
+        if !collisions.is_empty() {
+            return Err(Error::User(User::CragMergeConflict(collisions)));
+        }
+
+        drop(statement);
+
+        let count = crag_ascent_count(&tx, from_id)?;
+
+        tx.execute(
             "
-            SELECT date
-            FROM ascents
-            WHERE route = ? AND grade = ? AND crag = ?
+            UPDATE ascents
+            SET route_id = (
+                SELECT into_routes.id
+                FROM routes AS from_routes
+                JOIN routes AS into_routes
+                    ON into_routes.name = from_routes.name
+                    AND into_routes.grade = from_routes.grade
+                    AND into_routes.scale = from_routes.scale
+                WHERE from_routes.id = ascents.route_id AND into_routes.crag_id = ?2
+            )
+            WHERE route_id IN (
+                SELECT from_routes.id
+                FROM routes AS from_routes
+                JOIN routes AS into_routes
+                    ON into_routes.name = from_routes.name
+                    AND into_routes.grade = from_routes.grade
+                    AND into_routes.scale = from_routes.scale
+                WHERE from_routes.crag_id = ?1 AND into_routes.crag_id = ?2
+            )
             ",
+            (from_id, into_id),
         )?;
 
-        let mut rows = statement.query_map((&route.name, &route.grade, &route.crag), |row| {
-            row.get::<usize, String>(0)
-        })?;
+        // Whatever routes are left at the first crag have no twin to merge
+        // with, so just move over
+        tx.execute(
+            "
+            UPDATE routes
+            SET crag_id = ?2
+            WHERE crag_id = ?1 AND id IN (SELECT route_id FROM ascents)
+            ",
+            (from_id, into_id),
+        )?;
 
-        match rows.next() {
-            None => Err(Error::User(User::AscentNotFound)),
-            Some(date) => Ok(Ascent::new(
-                route,
-                Date::parse(&date?, utils::DATE_FORMAT).expect("Should be able to parse date"),
-            )),
-        }
+        delete_unreferenced_routes_and_crags(&tx)?;
+
+        tx.commit()?;
+
+        Ok(count)
     }
 
-    pub fn drop_ascent(&self, route: &Route) -> Result<()> {
-        let mut statement = self.connection.prepare(
+    /// Looks up the id of a crag, adding the crag if it is new
+    fn resolve_crag_id(&self, crag: &str) -> Result<i64> {
+        if let Some(crag_id) = find_crag_id(&self.connection, crag)? {
+            return Ok(crag_id);
+        }
+
+        self.connection.execute(
             "
-            SELECT 1
-            FROM ascents
-            WHERE route = ? AND grade = ? AND crag = ?
+            INSERT INTO crags(name)
+            VALUES(?)
             ",
+            [crag],
         )?;
 
-        let exists = statement.exists((&route.name, &route.grade, &route.crag))?;
+        Ok(self.connection.last_insert_rowid())
+    }
 
-        if !exists {
-            return Err(Error::User(User::AscentNotFound));
+    fn find_route_id(&self, route: &Route) -> Result<Option<i64>> {
+        let route_id = self
+            .connection
+            .query_row(
+                "
+                SELECT routes.id
+                FROM routes
+                JOIN crags ON crags.id = routes.crag_id
+                WHERE routes.name = ? AND grade = ? AND scale = ? AND crags.name = ?
+                ",
+                (&route.name, &route.grade, &route.scale, &route.crag),
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(route_id)
+    }
+
+    /// Looks up the id of a route, adding the route (and its crag) if it
+    /// is new
+    fn resolve_route_id(&self, route: &Route) -> Result<i64> {
+        if let Some(route_id) = self.find_route_id(route)? {
+            return Ok(route_id);
         }
 
+        let crag_id = self.resolve_crag_id(&route.crag)?;
+
         self.connection.execute(
             "
-            DELETE FROM ascents
-            WHERE route = ? AND grade = ? AND crag = ?
+            INSERT INTO routes(name, grade, scale, crag_id)
+            VALUES(?, ?, ?, ?)
             ",
-            (&route.name, &route.grade, &route.crag),
+            (&route.name, &route.grade, &route.scale, crag_id),
         )?;
 
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    pub fn log_ascent(&self, ascent: &Ascent) -> Result<()> {
+        let tx = self.connection.unchecked_transaction()?;
+
+        self.insert_ascent(ascent)?;
+
+        tx.commit()?;
+
         Ok(())
     }
 
-    pub fn total_count(&self) -> Result<u32> {
-        let total_count = self.connection.query_row(
+    /// Logs many ascents at once, all or none of them, skipping any that are
+    /// already logged and returning the ones that weren't. A dry run rolls
+    /// back instead, leaving the database as it was.
+    pub fn log_ascents<'a>(&self, ascents: &'a [Ascent], dry_run: bool) -> Result<Vec<&'a Ascent>> {
+        let tx = self.connection.unchecked_transaction()?;
+        let mut logged = Vec::new();
+
+        for ascent in ascents {
+            match self.insert_ascent(ascent) {
+                Ok(()) => logged.push(ascent),
+                Err(Error::User(User::AscentAlreadyLogged(_))) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !dry_run {
+            tx.commit()?;
+        }
+
+        Ok(logged)
+    }
+
+    // Inserts an ascent, along with its route and crag if they are new,
+    // leaving it to the caller to do so in a transaction
+    fn insert_ascent(&self, ascent: &Ascent) -> Result<()> {
+        // Repeats of a route are fine, but not on the same day
+        if self.ascent_exists(ascent)? {
+            let date = format_date(ascent.date);
+            return Err(Error::User(User::AscentAlreadyLogged(date)));
+        }
+
+        let route_id = self.resolve_route_id(&ascent.route)?;
+
+        self.connection.execute(
             "
-            SELECT count(*)
-            FROM ascents
+            INSERT INTO ascents(route_id, date, style, notes)
+            VALUES(?, ?, ?, ?)
             ",
-            (),
-            |row| row.get(0),
+            (
+                route_id,
+                format_date(ascent.date),
+                ascent.style.name(),
+                &ascent.notes,
+            ),
         )?;
 
-        Ok(total_count)
+        Ok(())
     }
 
-    pub fn year_counts(&self) -> Result<Vec<Count>> {
-        let statement = self.connection.prepare(
+    fn ascent_exists(&self, ascent: &Ascent) -> Result<bool> {
+        let route_id = match self.find_route_id(&ascent.route)? {
+            Some(route_id) => route_id,
+            None => return Ok(false),
+        };
+
+        let mut statement = self.connection.prepare(
             "
-            SELECT strftime('%Y', date) AS year, count(*)
+            SELECT 1
             FROM ascents
-            GROUP BY year
-            ORDER BY year
+            WHERE route_id = ? AND date = ?
             ",
         )?;
 
-        gather_counts(statement)
+        let exists = statement.exists((route_id, format_date(ascent.date)))?;
+
+        Ok(exists)
     }
 
-    pub fn crag_counts(&self) -> Result<Vec<Count>> {
-        let statement = self.connection.prepare(
+    /// Finds every ascent of a route, from earliest to latest
+    pub fn find_ascents(&self, route: &Route) -> Result<Vec<Ascent>> {
+        let mut ascents = Vec::new();
+
+        let route_id = match self.find_route_id(route)? {
+            Some(route_id) => route_id,
+            None => return Err(Error::User(User::AscentNotFound)),
+        };
+
+        let mut statement = self.connection.prepare(
             "
-            SELECT crag, count(*)
+            SELECT routes.name, grade, scale, crags.name, date, style, notes
             FROM ascents
-            GROUP BY crag
-            ORDER BY crag
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN crags ON crags.id = routes.crag_id
+            WHERE route_id = ?
+            ORDER BY date
             ",
         )?;
 
-        gather_counts(statement)
+        let rows = statement.query_map([route_id], ascent_from_row)?;
+
+        for ascent in rows {
+            ascents.push(ascent?);
+        }
+
+        if ascents.is_empty() {
+            return Err(Error::User(User::AscentNotFound));
+        }
+
+        Ok(ascents)
     }
 
-    pub fn grade_counts(&self) -> Result<Vec<Count>> {
-        let statement = self.connection.prepare(
+    pub fn drop_ascent(&self, ascent: &Ascent) -> Result<()> {
+        if !self.ascent_exists(ascent)? {
+            return Err(Error::User(User::AscentNotFound));
+        }
+
+        let route_id = self.find_route_id(&ascent.route)?;
+
+        let tx = self.connection.unchecked_transaction()?;
+
+        tx.execute(
             "
-            SELECT grade_counts.grade, grade_counts.count
-            FROM (
-                SELECT grade, count(*) AS count
-                FROM ascents
-                GROUP BY grade
-            ) AS grade_counts
-            LEFT JOIN grade_info USING(grade)
-            ORDER BY grade_info.grade_number, grade_info.grade_letter
+            DELETE FROM ascents
+            WHERE route_id = ? AND date = ?
             ",
+            (route_id, format_date(ascent.date)),
         )?;
 
-        gather_counts(statement)
-    }
-}
+        delete_unreferenced_routes_and_crags(&tx)?;
 
-fn format_date(date: Date) -> String {
-    date.format(utils::DATE_FORMAT)
-        .expect("Should be able to format date")
-}
+        tx.commit()?;
 
-fn gather_counts(mut statement: rusqlite::Statement) -> Result<Vec<Count>> {
-    let mut counts = Vec::new();
+        Ok(())
+    }
 
-    let rows = statement.query_map((), |row| {
-        Ok(Count {
-            category: row.get(0)?,
-            value: row.get(1)?,
-        })
-    })?;
+    /// Replaces a logged ascent with an edited version of it, moving it to
+    /// a different route (and crag) if any of those fields changed
+    pub fn update_ascent(&self, ascent: &Ascent, updated: &Ascent) -> Result<()> {
+        let route_id = match self.find_route_id(&ascent.route)? {
+            Some(route_id) if self.ascent_exists(ascent)? => route_id,
+            _ => return Err(Error::User(User::AscentNotFound)),
+        };
 
-    for count in rows {
-        counts.push(count?);
-    }
+        let moved = updated.route != ascent.route || updated.date != ascent.date;
 
-    Ok(counts)
-}
+        if moved && self.ascent_exists(updated)? {
+            let date = format_date(updated.date);
+            return Err(Error::User(User::AscentAlreadyLogged(date)));
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use time::macros::date;
+        let tx = self.connection.unchecked_transaction()?;
+
+        let updated_route_id = self.resolve_route_id(&updated.route)?;
+
+        tx.execute(
+            "
+            UPDATE ascents
+            SET route_id = ?, date = ?, style = ?, notes = ?
+            WHERE route_id = ? AND date = ?
+            ",
+            (
+                updated_route_id,
+                format_date(updated.date),
+                updated.style.name(),
+                &updated.notes,
+                route_id,
+                format_date(ascent.date),
+            ),
+        )?;
+
+        delete_unreferenced_routes_and_crags(&tx)?;
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Finds every ascent matching a filter, in the given order
+    pub fn list_ascents(&self, filter: &AscentFilter, sort: Sort) -> Result<Vec<Ascent>> {
+        let mut statement = self.connection.prepare(&format!(
+            "
+            SELECT routes.name, routes.grade, routes.scale, crags.name, date, style, notes
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN crags ON crags.id = routes.crag_id
+            JOIN grade_info
+                ON grade_info.grade = routes.grade
+                AND grade_info.scale = routes.scale
+            WHERE ascents.id IN ({FILTERED_ASCENT_IDS})
+            ORDER BY {}
+            ",
+            sort.order_by(),
+        ))?;
+
+        let rows = statement.query_map(params_from_iter(filter.params(&[])?), ascent_from_row)?;
+
+        let mut ascents = Vec::new();
+
+        for ascent in rows {
+            ascents.push(ascent?);
+        }
+
+        Ok(ascents)
+    }
+
+    /// Finds every ascent whose route, crag or notes match some text, from
+    /// best to worst match
+    pub fn search_ascents(&self, text: &str) -> Result<Vec<Ascent>> {
+        // Each word is quoted to keep FTS5 from reading it as query syntax,
+        // and can match the start of a longer word
+        let query: Vec<String> = text
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect();
+
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut statement = self.connection.prepare(
+            "
+            SELECT routes.name, grade, scale, crags.name, date, style, ascents.notes
+            FROM ascent_search
+            JOIN ascents ON ascents.id = ascent_search.rowid
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN crags ON crags.id = routes.crag_id
+            WHERE ascent_search MATCH ?
+            ORDER BY rank, date
+            ",
+        )?;
+
+        let rows = statement.query_map([query.join(" ")], ascent_from_row)?;
+
+        let mut ascents = Vec::new();
+
+        for ascent in rows {
+            ascents.push(ascent?);
+        }
+
+        Ok(ascents)
+    }
+
+    pub fn total_count(&self, filter: &AscentFilter) -> Result<u32> {
+        let total_count = self.connection.query_row(
+            &format!(
+                "
+                SELECT count(*)
+                FROM ascents
+                WHERE id IN ({FILTERED_ASCENT_IDS})
+                "
+            ),
+            params_from_iter(filter.params(&[])?),
+            |row| row.get(0),
+        )?;
+
+        Ok(total_count)
+    }
+
+    pub fn unique_route_count(&self, filter: &AscentFilter) -> Result<u32> {
+        let unique_route_count = self.connection.query_row(
+            &format!(
+                "
+                SELECT count(DISTINCT route_id)
+                FROM ascents
+                WHERE id IN ({FILTERED_ASCENT_IDS})
+                "
+            ),
+            params_from_iter(filter.params(&[])?),
+            |row| row.get(0),
+        )?;
+
+        Ok(unique_route_count)
+    }
+
+    pub fn year_counts(&self, filter: &AscentFilter) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT strftime('%Y', date) AS year, count(*)
+            FROM ascents
+            WHERE id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY year
+            ORDER BY year
+            "
+        ))?;
+
+        gather_counts(statement, params_from_iter(filter.params(&[])?))
+    }
+
+    pub fn crag_counts(&self, filter: &AscentFilter) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT crags.name AS crag, count(*)
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN crags ON crags.id = routes.crag_id
+            WHERE ascents.id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY crag
+            ORDER BY crag
+            "
+        ))?;
+
+        gather_counts(statement, params_from_iter(filter.params(&[])?))
+    }
+
+    /// Counts the ascents in each month of the year, from "01" for January
+    /// to "12" for December, leaving out months without any
+    pub fn month_counts(&self, filter: &AscentFilter) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT strftime('%m', date) AS month, count(*)
+            FROM ascents
+            WHERE id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY month
+            ORDER BY month
+            "
+        ))?;
+
+        gather_counts(statement, params_from_iter(filter.params(&[])?))
+    }
+
+    /// Counts the ascents on each day of the week, from "0" for Sunday to
+    /// "6" for Saturday, leaving out days without any
+    pub fn weekday_counts(&self, filter: &AscentFilter) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT strftime('%w', date) AS weekday, count(*)
+            FROM ascents
+            WHERE id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY weekday
+            ORDER BY weekday
+            "
+        ))?;
+
+        gather_counts(statement, params_from_iter(filter.params(&[])?))
+    }
+
+    /// Counts the ascents on each date with any, in order
+    pub fn date_counts(&self, filter: &AscentFilter) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT date, count(*)
+            FROM ascents
+            WHERE id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY date
+            ORDER BY date
+            "
+        ))?;
+
+        gather_counts(statement, params_from_iter(filter.params(&[])?))
+    }
+
+    /// Counts the ascents at each crag in each year, by crag and then year
+    pub fn crag_year_counts(&self, filter: &AscentFilter) -> Result<Vec<(String, String, u32)>> {
+        let mut statement = self.connection.prepare(&format!(
+            "
+            SELECT crags.name AS crag, strftime('%Y', date) AS year, count(*)
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN crags ON crags.id = routes.crag_id
+            WHERE ascents.id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY crag, year
+            ORDER BY crag, year
+            "
+        ))?;
+
+        let rows = statement.query_map(params_from_iter(filter.params(&[])?), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+
+        let mut counts = Vec::new();
+
+        for count in rows {
+            counts.push(count?);
+        }
+
+        Ok(counts)
+    }
+
+    pub fn scales(&self, filter: &AscentFilter) -> Result<Vec<String>> {
+        let mut scales = Vec::new();
+
+        let mut statement = self.connection.prepare(&format!(
+            "
+            SELECT DISTINCT scale
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            WHERE ascents.id IN ({FILTERED_ASCENT_IDS})
+            "
+        ))?;
+
+        let rows = statement.query_map(params_from_iter(filter.params(&[])?), |row| row.get(0))?;
+
+        for scale in rows {
+            scales.push(scale?);
+        }
+
+        let scale_names = grades::scale_names();
+        scales.sort_by_key(|scale| scale_names.iter().position(|name| name == scale));
+
+        Ok(scales)
+    }
+
+    pub fn grade_counts(&self, filter: &AscentFilter, scale: &str) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT grade_counts.grade, grade_counts.count
+            FROM (
+                SELECT grade, scale, count(*) AS count
+                FROM ascents
+                JOIN routes ON routes.id = ascents.route_id
+                WHERE scale = ?9 AND ascents.id IN ({FILTERED_ASCENT_IDS})
+                GROUP BY grade
+            ) AS grade_counts
+            LEFT JOIN grade_info USING(grade, scale)
+            ORDER BY grade_info.grade_order
+            "
+        ))?;
+
+        gather_counts(statement, params_from_iter(filter.params(&[Some(scale)])?))
+    }
+
+    pub fn normalized_grade_counts(
+        &self,
+        filter: &AscentFilter,
+        display_scale: &str,
+    ) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT grade_counts.to_grade, grade_counts.count
+            FROM (
+                SELECT to_grade, to_scale, count(*) AS count
+                FROM ascents
+                JOIN routes ON routes.id = ascents.route_id
+                JOIN grade_conversions USING(grade, scale)
+                WHERE to_scale = ?9 AND ascents.id IN ({FILTERED_ASCENT_IDS})
+                GROUP BY to_grade
+            ) AS grade_counts
+            LEFT JOIN grade_info
+                ON grade_info.grade = grade_counts.to_grade
+                AND grade_info.scale = grade_counts.to_scale
+            ORDER BY grade_info.grade_order
+            "
+        ))?;
+
+        gather_counts(
+            statement,
+            params_from_iter(filter.params(&[Some(display_scale)])?),
+        )
+    }
+
+    /// Counts the clean leads of every grade in a scale, from the easiest
+    /// to the hardest one led, including the grades in between with none.
+    /// Alias grades, such as 5.10+ in YDS, count towards the grade they
+    /// stand for.
+    pub fn pyramid_counts(&self, filter: &AscentFilter, scale: &str) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT grade, count(*)
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            WHERE scale = ?9
+                AND style != 'toprope'
+                AND ascents.id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY grade
+            "
+        ))?;
+
+        let counts = gather_counts(statement, params_from_iter(filter.params(&[Some(scale)])?))?;
+
+        Ok(fill_pyramid(scale, counts))
+    }
+
+    pub fn normalized_pyramid_counts(
+        &self,
+        filter: &AscentFilter,
+        display_scale: &str,
+    ) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT to_grade, count(*)
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN grade_conversions USING(grade, scale)
+            WHERE to_scale = ?9
+                AND style != 'toprope'
+                AND ascents.id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY to_grade
+            "
+        ))?;
+
+        let counts = gather_counts(
+            statement,
+            params_from_iter(filter.params(&[Some(display_scale)])?),
+        )?;
+
+        Ok(fill_pyramid(display_scale, counts))
+    }
+
+    pub fn style_counts(&self, filter: &AscentFilter) -> Result<Vec<Count>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT style, count(*)
+            FROM ascents
+            WHERE id IN ({FILTERED_ASCENT_IDS})
+            GROUP BY style
+            "
+        ))?;
+
+        let mut counts = gather_counts(statement, params_from_iter(filter.params(&[])?))?;
+
+        let style_names = Style::names();
+        counts.sort_by_key(|count| style_names.iter().position(|name| name == count.category()));
+
+        Ok(counts)
+    }
+
+    /// Finds the period and grade of every ascent in a scale, along with the
+    /// order of the grade within the scale, from the earliest period to the
+    /// latest and from the easiest grade to the hardest
+    pub fn period_grades(
+        &self,
+        filter: &AscentFilter,
+        scale: &str,
+        period: Period,
+    ) -> Result<Vec<(String, String, u32)>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT strftime(?10, date) AS period, grade, grade_info.grade_order
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN grade_info USING(grade, scale)
+            WHERE scale = ?9 AND ascents.id IN ({FILTERED_ASCENT_IDS})
+            ORDER BY period, grade_info.grade_order
+            "
+        ))?;
+
+        let params = filter.params(&[Some(scale), Some(period.format())])?;
+        gather_period_grades(statement, params_from_iter(params))
+    }
+
+    /// Finds the period and grade of every ascent across every scale of the
+    /// same discipline as the display scale, with grades converted to the
+    /// display scale, in the same order as period_grades
+    pub fn normalized_period_grades(
+        &self,
+        filter: &AscentFilter,
+        display_scale: &str,
+        period: Period,
+    ) -> Result<Vec<(String, String, u32)>> {
+        let statement = self.connection.prepare(&format!(
+            "
+            SELECT strftime(?10, date) AS period, to_grade, grade_info.grade_order
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN grade_conversions USING(grade, scale)
+            JOIN grade_info
+                ON grade_info.grade = grade_conversions.to_grade
+                AND grade_info.scale = grade_conversions.to_scale
+            WHERE to_scale = ?9 AND ascents.id IN ({FILTERED_ASCENT_IDS})
+            ORDER BY period, grade_info.grade_order
+            "
+        ))?;
+
+        let params = filter.params(&[Some(display_scale), Some(period.format())])?;
+        gather_period_grades(statement, params_from_iter(params))
+    }
+
+    /// Finds the hardest ascent of a scale, optionally limited to a
+    /// style, preferring the earliest ascent on ties
+    pub fn hardest_ascent(
+        &self,
+        filter: &AscentFilter,
+        scale: &str,
+        style: Option<Style>,
+    ) -> Result<Option<Ascent>> {
+        let mut statement = self.connection.prepare(&format!(
+            "
+            SELECT routes.name, grade, scale, crags.name, date, style, notes
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN crags ON crags.id = routes.crag_id
+            JOIN grade_info USING(grade, scale)
+            WHERE scale = ?9
+                AND (?10 IS NULL OR style = ?10)
+                AND ascents.id IN ({FILTERED_ASCENT_IDS})
+            ORDER BY grade_info.grade_order DESC, date
+            LIMIT 1
+            "
+        ))?;
+
+        let params = filter.params(&[Some(scale), style.map(|s| s.name())])?;
+        let mut rows = statement.query_map(params_from_iter(params), ascent_from_row)?;
+
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Finds the hardest ascent across every scale of the same discipline as
+    /// the display scale, along with its grade converted to the display scale
+    pub fn normalized_hardest_ascent(
+        &self,
+        filter: &AscentFilter,
+        display_scale: &str,
+        style: Option<Style>,
+    ) -> Result<Option<(Ascent, String)>> {
+        let mut statement = self.connection.prepare(&format!(
+            "
+            SELECT routes.name, grade, scale, crags.name, date, style, notes, to_grade
+            FROM ascents
+            JOIN routes ON routes.id = ascents.route_id
+            JOIN crags ON crags.id = routes.crag_id
+            JOIN grade_info USING(grade, scale)
+            JOIN grade_conversions USING(grade, scale)
+            WHERE to_scale = ?9
+                AND (?10 IS NULL OR style = ?10)
+                AND ascents.id IN ({FILTERED_ASCENT_IDS})
+            ORDER BY grade_info.difficulty DESC, date
+            LIMIT 1
+            "
+        ))?;
+
+        let params = filter.params(&[Some(display_scale), style.map(|s| s.name())])?;
+        let mut rows = statement.query_map(params_from_iter(params), |row| {
+            Ok((ascent_from_row(row)?, row.get(7)?))
+        })?;
+
+        Ok(rows.next().transpose()?)
+    }
+}
+
+fn find_crag_id(conn: &Connection, crag: &str) -> Result<Option<i64>> {
+    let crag_id = conn
+        .query_row(
+            "
+            SELECT id
+            FROM crags
+            WHERE name = ?
+            ",
+            [crag],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(crag_id)
+}
+
+fn crag_ascent_count(conn: &Connection, crag_id: i64) -> Result<usize> {
+    let count = conn.query_row(
+        "
+        SELECT count(*)
+        FROM ascents
+        JOIN routes ON routes.id = ascents.route_id
+        WHERE crag_id = ?
+        ",
+        [crag_id],
+        |row| row.get(0),
+    )?;
+
+    Ok(count)
+}
+
+// Routes and crags only exist to be referenced by ascents, so clean up any
+// left behind without one
+fn delete_unreferenced_routes_and_crags(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        DELETE FROM routes
+        WHERE id NOT IN (SELECT route_id FROM ascents);
+
+        DELETE FROM crags
+        WHERE id NOT IN (SELECT crag_id FROM routes);
+        ",
+    )?;
+
+    Ok(())
+}
+
+fn format_date(date: Date) -> String {
+    date.format(utils::DATE_FORMAT)
+        .expect("Should be able to format date")
+}
+
+fn ascent_from_row(row: &rusqlite::Row) -> rusqlite::Result<Ascent> {
+    let route = Route {
+        name: row.get(0)?,
+        grade: row.get(1)?,
+        scale: row.get(2)?,
+        crag: row.get(3)?,
+    };
+
+    let date: String = row.get(4)?;
+    let date = Date::parse(&date, utils::DATE_FORMAT).expect("Should be able to parse date");
+
+    let style: String = row.get(5)?;
+    let style = Style::new(&style).expect("Should be able to parse style");
+
+    Ok(Ascent::new(route, date, style).with_notes(row.get(6)?))
+}
+
+fn gather_counts(mut statement: rusqlite::Statement, params: impl Params) -> Result<Vec<Count>> {
+    let mut counts = Vec::new();
+
+    let rows = statement.query_map(params, |row| Ok(Count::new(row.get(0)?, row.get(1)?)))?;
+
+    for count in rows {
+        counts.push(count?);
+    }
+
+    Ok(counts)
+}
+
+// Adds up counts of grades in a scale by the grade each one stands for,
+// with every such grade from the easiest to the hardest counted
+fn fill_pyramid(scale: &str, counts: Vec<Count>) -> Vec<Count> {
+    let scale = grades::find_scale(scale).expect("Counted scale should be known");
+
+    let mut levels: Vec<Count> = scale
+        .grades()
+        .iter()
+        .filter(|grade| scale.canonical_grade(grade.value()).as_ref() == Some(grade.value()))
+        .map(|grade| Count::new(grade.value().clone(), 0))
+        .collect();
+
+    for count in counts {
+        let canonical = scale
+            .canonical_grade(count.category())
+            .expect("Counted grade should be valid");
+
+        if let Some(level) = levels.iter_mut().find(|level| level.category == canonical) {
+            level.value += count.value;
+        }
+    }
+
+    let Some(first) = levels.iter().position(|level| level.value > 0) else {
+        return Vec::new();
+    };
+    let last = levels
+        .iter()
+        .rposition(|level| level.value > 0)
+        .expect("Some level has a count");
+
+    levels.drain(first..=last).collect()
+}
+
+fn gather_period_grades(
+    mut statement: rusqlite::Statement,
+    params: impl Params,
+) -> Result<Vec<(String, String, u32)>> {
+    let mut period_grades = Vec::new();
+
+    let rows = statement.query_map(params, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+
+    for period_grade in rows {
+        period_grades.push(period_grade?);
+    }
+
+    Ok(period_grades)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grades::Discipline;
+    use time::macros::date;
 
     #[test]
     fn invalid_grade() {
         let invalid_grades = [
-            "5.9+".to_string(),
-            "5.10".to_string(),
-            "5.11a/b".to_string(),
-            "5.12-".to_string(),
+            "5.9a".to_string(),
+            "5.16a".to_string(),
+            "5.10e".to_string(),
+            "5.11a/c".to_string(),
+            "5.12++".to_string(),
+            "5.10a+".to_string(),
         ];
 
         for invalid_grade in invalid_grades {
             let result = Route::new(
                 "Some Route".to_string(),
                 invalid_grade,
+                "yds".to_string(),
                 "Some Crag".to_string(),
             );
 
@@ -296,193 +1516,991 @@ mod tests {
     }
 
     #[test]
-    fn valid_grade() {
-        let valid_grades = [
-            "5.0".to_string(),
-            "5.9".to_string(),
-            "5.10a".to_string(),
-            "5.11d".to_string(),
-        ];
+    fn invalid_scale() {
+        let result = Route::new(
+            "Some Route".to_string(),
+            "5.10a".to_string(),
+            "not-a-scale".to_string(),
+            "Some Crag".to_string(),
+        );
+
+        assert_eq!(result.unwrap_err(), Error::User(User::InvalidScale));
+    }
+
+    #[test]
+    fn style() {
+        for name in Style::names() {
+            assert_eq!(Style::new(name).unwrap().name(), name);
+        }
+
+        assert_eq!(
+            Style::new("hangdog").unwrap_err(),
+            Error::User(User::InvalidStyle),
+        );
+    }
+
+    #[test]
+    fn valid_grade() {
+        let valid_grades = [
+            "5.0".to_string(),
+            "5.9".to_string(),
+            "5.10a".to_string(),
+            "5.11d".to_string(),
+            "5.9+".to_string(),
+            "5.7-".to_string(),
+            "5.10".to_string(),
+            "5.11a/b".to_string(),
+            "5.12-".to_string(),
+            "5.13+".to_string(),
+        ];
+
+        for valid_grade in valid_grades {
+            let result = Route::new(
+                "Some Route".to_string(),
+                valid_grade,
+                "yds".to_string(),
+                "Some Crag".to_string(),
+            );
+
+            assert!(result.is_ok());
+        }
+    }
+
+    fn ascents() -> [Ascent; 12] {
+        [
+            Ascent::new(
+                Route::new(
+                    "Classic Route".to_string(),
+                    "5.12a".to_string(),
+                    "yds".to_string(),
+                    "Some Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2023 - 01 - 01),
+                Style::Redpoint,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Some Other Route".to_string(),
+                    "5.9".to_string(),
+                    "yds".to_string(),
+                    "Some Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2022 - 01 - 01),
+                Style::Onsight,
+            ),
+            Ascent::new(
+                Route::new(
+                    "New Route".to_string(),
+                    "5.10d".to_string(),
+                    "yds".to_string(),
+                    "New Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2022 - 01 - 01),
+                Style::Redpoint,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Another Route".to_string(),
+                    "5.10a".to_string(),
+                    "yds".to_string(),
+                    "Another Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2023 - 01 - 01),
+                Style::Flash,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Some Route".to_string(),
+                    "5.7".to_string(),
+                    "yds".to_string(),
+                    "Some Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2023 - 01 - 01),
+                Style::Toprope,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Old Route".to_string(),
+                    "5.11a".to_string(),
+                    "yds".to_string(),
+                    "Old Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2022 - 01 - 01),
+                Style::Pinkpoint,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Cool Route".to_string(),
+                    "5.10a".to_string(),
+                    "yds".to_string(),
+                    "Some Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2022 - 01 - 01),
+                Style::Onsight,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Last Route".to_string(),
+                    "5.7".to_string(),
+                    "yds".to_string(),
+                    "Old Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2023 - 01 - 01),
+                Style::Redpoint,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Plus Route".to_string(),
+                    "5.10+".to_string(),
+                    "yds".to_string(),
+                    "New Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2023 - 01 - 01),
+                Style::Redpoint,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Slash Route".to_string(),
+                    "5.11a/b".to_string(),
+                    "yds".to_string(),
+                    "Another Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2022 - 01 - 01),
+                Style::Onsight,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Euro Route".to_string(),
+                    "6b+".to_string(),
+                    "french".to_string(),
+                    "Old Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2023 - 01 - 01),
+                Style::Flash,
+            ),
+            Ascent::new(
+                Route::new(
+                    "Boulder Problem".to_string(),
+                    "V4".to_string(),
+                    "v".to_string(),
+                    "Some Crag".to_string(),
+                )
+                .unwrap(),
+                date!(2022 - 01 - 01),
+                Style::Flash,
+            ),
+        ]
+    }
+
+    // Filter that every ascent meets
+    fn everything() -> AscentFilter {
+        AscentFilter::default()
+    }
+
+    fn set_up_test_db() -> AscentDB {
+        let test_db = "test.db".to_string();
+
+        if !utils::exists(&test_db) {
+            panic!("{test_db} must be initialized to test");
+        }
+
+        let conn = Connection::open(&test_db).unwrap();
+        conn.execute_batch(
+            "
+            DELETE FROM ascents;
+            DELETE FROM routes;
+            DELETE FROM crags;
+            ",
+        )
+        .unwrap();
+
+        let db = AscentDB::new(&test_db).unwrap();
+
+        for ascent in &ascents() {
+            db.log_ascent(ascent).unwrap();
+        }
+
+        db
+    }
+
+    #[test]
+    fn crags() {
+        let db = set_up_test_db();
+
+        let expected = vec![
+            "Another Crag".to_string(),
+            "New Crag".to_string(),
+            "Old Crag".to_string(),
+            "Some Crag".to_string(),
+        ];
+
+        assert_eq!(db.crags().unwrap(), expected);
+    }
+
+    #[test]
+    fn log_ascent() {
+        let db = set_up_test_db();
+
+        for ascent in &ascents() {
+            assert_eq!(
+                db.log_ascent(ascent).unwrap_err(),
+                Error::User(User::AscentAlreadyLogged(format_date(ascent.date))),
+            );
+        }
+    }
+
+    #[test]
+    fn log_repeat_ascent() {
+        let db = set_up_test_db();
+
+        let first = &ascents()[0];
+        let repeat = Ascent::new(
+            Route::new(
+                first.route.name.clone(),
+                first.route.grade.clone(),
+                first.route.scale.clone(),
+                first.route.crag.clone(),
+            )
+            .unwrap(),
+            date!(2024 - 06 - 01),
+            Style::Redpoint,
+        );
+
+        assert!(db.log_ascent(&repeat).is_ok());
+        assert_eq!(
+            db.find_ascents(&first.route).unwrap(),
+            vec![ascents()[0].clone(), repeat],
+        );
+        assert_eq!(db.total_count(&everything()).unwrap(), 13);
+        assert_eq!(db.unique_route_count(&everything()).unwrap(), 12);
+    }
+
+    #[test]
+    fn find_ascents() {
+        let db = set_up_test_db();
+
+        for ascent in ascents() {
+            let route = Route::new(
+                ascent.route.name.clone(),
+                ascent.route.grade.clone(),
+                ascent.route.scale.clone(),
+                ascent.route.crag.clone(),
+            )
+            .unwrap();
+
+            assert_eq!(vec![ascent], db.find_ascents(&route).unwrap());
+        }
+
+        let route = Route::new(
+            "Non-existent route".to_string(),
+            "5.7".to_string(),
+            "yds".to_string(),
+            "Non-existent crag".to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            db.find_ascents(&route).unwrap_err(),
+            Error::User(User::AscentNotFound),
+        );
+    }
+
+    #[test]
+    fn drop_ascent() {
+        let db = set_up_test_db();
+
+        for ascent in &ascents() {
+            assert!(db.drop_ascent(ascent).is_ok());
+            assert_eq!(
+                db.drop_ascent(ascent).unwrap_err(),
+                Error::User(User::AscentNotFound),
+            );
+        }
+    }
+
+    #[test]
+    fn update_ascent() {
+        let db = set_up_test_db();
+
+        let ascent = &ascents()[5];
+        let updated = Ascent::new(
+            Route::new(
+                "Renamed Route".to_string(),
+                "5.11b".to_string(),
+                "yds".to_string(),
+                "Renamed Crag".to_string(),
+            )
+            .unwrap(),
+            date!(2022 - 02 - 01),
+            Style::Redpoint,
+        );
+
+        assert!(db.update_ascent(ascent, &updated).is_ok());
+        assert_eq!(
+            db.find_ascents(&ascent.route).unwrap_err(),
+            Error::User(User::AscentNotFound),
+        );
+        assert_eq!(
+            db.find_ascents(&updated.route).unwrap(),
+            vec![updated.clone()]
+        );
+        assert_eq!(db.total_count(&everything()).unwrap(), 12);
+        assert_eq!(db.unique_route_count(&everything()).unwrap(), 12);
+        assert!(db.crags().unwrap().contains(&"Renamed Crag".to_string()));
+
+        // Only the style changing keeps the ascent where it is
+        let restyled = Ascent::new(updated.route.clone(), updated.date, Style::Onsight);
+        assert!(db.update_ascent(&updated, &restyled).is_ok());
+        assert_eq!(
+            db.find_ascents(&updated.route).unwrap(),
+            vec![restyled.clone()]
+        );
+
+        assert_eq!(
+            db.update_ascent(&restyled, &ascents()[0]).unwrap_err(),
+            Error::User(User::AscentAlreadyLogged("2023-01-01".to_string())),
+        );
+        assert_eq!(
+            db.update_ascent(ascent, &updated).unwrap_err(),
+            Error::User(User::AscentNotFound),
+        );
+    }
+
+    #[test]
+    fn list_ascents() {
+        let db = set_up_test_db();
+
+        let names = |filter: &AscentFilter, sort: Sort| -> Vec<String> {
+            db.list_ascents(filter, sort)
+                .unwrap()
+                .iter()
+                .map(|ascent| ascent.route.name.clone())
+                .collect()
+        };
+
+        let all = db.list_ascents(&everything(), Sort::Date).unwrap();
+        assert_eq!(all.len(), 12);
+        assert!(all.windows(2).all(|pair| pair[0].date <= pair[1].date));
+
+        // Boulders never sort between routes by grade
+        let disciplines: Vec<Discipline> = db
+            .list_ascents(&everything(), Sort::Grade)
+            .unwrap()
+            .iter()
+            .map(|ascent| {
+                grades::find_scale(&ascent.route.scale)
+                    .unwrap()
+                    .discipline()
+            })
+            .collect();
+        assert!(disciplines
+            .windows(2)
+            .all(|pair| pair != [Discipline::Boulder, Discipline::Route]));
+
+        let filter = AscentFilter {
+            crag: Some("some crag".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&filter, Sort::Grade),
+            [
+                "Some Route",
+                "Some Other Route",
+                "Cool Route",
+                "Classic Route",
+                "Boulder Problem",
+            ],
+        );
+
+        let filter = AscentFilter {
+            route: Some("OTHER".to_string()),
+            year: Some(2022),
+            ..Default::default()
+        };
+        assert_eq!(names(&filter, Sort::Date), ["Some Other Route"]);
+
+        let filter = AscentFilter {
+            since: Some(date!(2022 - 01 - 02)),
+            until: Some(date!(2022 - 12 - 31)),
+            ..Default::default()
+        };
+        assert!(names(&filter, Sort::Date).is_empty());
+
+        // French routes are converted to compare against YDS bounds, while
+        // boulders are left out entirely
+        let filter = AscentFilter {
+            grade_min: Some("5.10d".to_string()),
+            grade_max: Some("5.11a".to_string()),
+            ..Default::default()
+        };
+        let mut listed = names(&filter, Sort::Grade);
+        listed.sort();
+        assert_eq!(listed, ["Euro Route", "New Route", "Old Route"]);
+
+        let filter = AscentFilter {
+            grade_min: Some("5.10z".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            db.list_ascents(&filter, Sort::Date).unwrap_err(),
+            Error::User(User::InvalidGrade("YDS")),
+        );
+    }
+
+    #[test]
+    fn filtered_counts() {
+        let db = set_up_test_db();
+
+        let filter = AscentFilter {
+            crag: Some("some crag".to_string()),
+            year: Some(2023),
+            ..Default::default()
+        };
+
+        assert_eq!(db.total_count(&filter).unwrap(), 2);
+        assert_eq!(db.unique_route_count(&filter).unwrap(), 2);
+        assert_eq!(
+            db.year_counts(&filter).unwrap(),
+            [Count::new("2023".to_string(), 2)],
+        );
+        assert_eq!(
+            db.crag_counts(&filter).unwrap(),
+            [Count::new("Some Crag".to_string(), 2)],
+        );
+        assert_eq!(
+            db.style_counts(&filter).unwrap(),
+            [
+                Count::new("redpoint".to_string(), 1),
+                Count::new("toprope".to_string(), 1),
+            ],
+        );
+        assert_eq!(db.scales(&filter).unwrap(), ["yds"]);
+        assert_eq!(
+            db.grade_counts(&filter, "yds").unwrap(),
+            [
+                Count::new("5.7".to_string(), 1),
+                Count::new("5.12a".to_string(), 1),
+            ],
+        );
+        assert_eq!(
+            db.hardest_ascent(&filter, "yds", Some(Style::Toprope))
+                .unwrap()
+                .unwrap()
+                .route()
+                .name(),
+            "Some Route",
+        );
+
+        // The French 6b+ converts to a 5.11a, while the V4 has no YDS grade
+        let filter = AscentFilter {
+            grade_min: Some("5.11a".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(db.total_count(&filter).unwrap(), 4);
+        assert_eq!(db.scales(&filter).unwrap(), ["yds", "french"]);
+        assert_eq!(
+            db.normalized_grade_counts(&filter, "yds").unwrap(),
+            [
+                Count::new("5.11a".to_string(), 2),
+                Count::new("5.11a/b".to_string(), 1),
+                Count::new("5.12a".to_string(), 1),
+            ],
+        );
+    }
+
+    #[test]
+    fn period_grades() {
+        let db = set_up_test_db();
+
+        let filter = AscentFilter {
+            crag: Some("Old Crag".to_string()),
+            ..Default::default()
+        };
+
+        let period_grades: Vec<(String, String)> = db
+            .period_grades(&filter, "yds", Period::Month)
+            .unwrap()
+            .into_iter()
+            .map(|(period, grade, _)| (period, grade))
+            .collect();
+
+        assert_eq!(
+            period_grades,
+            [
+                ("2022-01".to_string(), "5.11a".to_string()),
+                ("2023-01".to_string(), "5.7".to_string()),
+            ],
+        );
+
+        // The French 6b+ is converted to a 5.11a
+        let period_grades: Vec<(String, String)> = db
+            .normalized_period_grades(&filter, "yds", Period::Year)
+            .unwrap()
+            .into_iter()
+            .map(|(period, grade, _)| (period, grade))
+            .collect();
+
+        assert_eq!(
+            period_grades,
+            [
+                ("2022".to_string(), "5.11a".to_string()),
+                ("2023".to_string(), "5.7".to_string()),
+                ("2023".to_string(), "5.11a".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn crag_year_counts() {
+        let db = set_up_test_db();
+
+        let filter = AscentFilter {
+            grade_min: Some("5.10d".to_string()),
+            ..Default::default()
+        };
+
+        let expected = [
+            ("Another Crag", "2022", 1),
+            ("New Crag", "2022", 1),
+            ("Old Crag", "2022", 1),
+            ("Old Crag", "2023", 1),
+            ("Some Crag", "2023", 1),
+        ]
+        .map(|(crag, year, count)| (crag.to_string(), year.to_string(), count));
+
+        assert_eq!(db.crag_year_counts(&filter).unwrap(), expected);
+    }
+
+    #[test]
+    fn calendar_counts() {
+        let db = set_up_test_db();
+
+        let filter = AscentFilter {
+            crag: Some("Old Crag".to_string()),
+            ..Default::default()
+        };
+
+        // 2022-01-01 was a Saturday, and 2023-01-01 a Sunday
+        assert_eq!(
+            db.month_counts(&filter).unwrap(),
+            [Count::new("01".to_string(), 3)],
+        );
+        assert_eq!(
+            db.weekday_counts(&filter).unwrap(),
+            [
+                Count::new("0".to_string(), 2),
+                Count::new("6".to_string(), 1),
+            ],
+        );
+        assert_eq!(
+            db.date_counts(&filter).unwrap(),
+            [
+                Count::new("2022-01-01".to_string(), 1),
+                Count::new("2023-01-01".to_string(), 2),
+            ],
+        );
+
+        let filter = AscentFilter {
+            year: Some(2023),
+            ..filter
+        };
+        assert_eq!(
+            db.date_counts(&filter).unwrap(),
+            [Count::new("2023-01-01".to_string(), 2)],
+        );
+    }
+
+    #[test]
+    fn search_ascents() {
+        let db = set_up_test_db();
+
+        let noted = Ascent::new(
+            Route::new(
+                "Crack Line".to_string(),
+                "5.9".to_string(),
+                "yds".to_string(),
+                "Céüse".to_string(),
+            )
+            .unwrap(),
+            date!(2024 - 07 - 01),
+            Style::Onsight,
+        )
+        .with_notes(Some(
+            "Perfect \"splitter\" crack, bring doubles".to_string(),
+        ));
+        db.log_ascent(&noted).unwrap();
+
+        let names = |text: &str| -> Vec<String> {
+            db.search_ascents(text)
+                .unwrap()
+                .iter()
+                .map(|ascent| ascent.route.name.clone())
+                .collect()
+        };
+
+        assert_eq!(db.search_ascents("splitter").unwrap(), vec![noted.clone()]);
+        assert_eq!(names("ceuse"), ["Crack Line"]);
+        assert_eq!(names("\"Split"), ["Crack Line"]);
+        assert_eq!(names("cla"), ["Classic Route"]);
+        assert_eq!(names("old euro"), ["Euro Route"]);
+        assert!(names("").is_empty());
+        assert!(names("nowhere").is_empty());
+
+        // Edits are picked up by the index
+        let renamed = Ascent::new(
+            Route::new(
+                "Splitter".to_string(),
+                "5.9".to_string(),
+                "yds".to_string(),
+                "Céüse".to_string(),
+            )
+            .unwrap(),
+            noted.date,
+            noted.style,
+        );
+        db.update_ascent(&noted, &renamed).unwrap();
+
+        assert_eq!(names("crack"), Vec::<String>::new());
+        assert_eq!(names("splitter"), ["Splitter"]);
+
+        db.drop_ascent(&renamed).unwrap();
+        assert!(names("splitter").is_empty());
+    }
+
+    #[test]
+    fn rename_crag() {
+        let db = set_up_test_db();
+
+        assert_eq!(db.rename_crag("Old Crag", "Ancient Crag").unwrap(), 3);
+        assert_eq!(
+            db.crags().unwrap(),
+            ["Ancient Crag", "Another Crag", "New Crag", "Some Crag"],
+        );
+        assert_eq!(db.search_ascents("ancient").unwrap().len(), 3);
+
+        assert_eq!(
+            db.rename_crag("Old Crag", "Older Crag").unwrap_err(),
+            Error::User(User::CragNotFound("Old Crag".to_string())),
+        );
+        assert_eq!(
+            db.rename_crag("Ancient Crag", "New Crag").unwrap_err(),
+            Error::User(User::CragAlreadyExists("New Crag".to_string())),
+        );
+    }
+
+    #[test]
+    fn merge_crags() {
+        let db = set_up_test_db();
+
+        // A misspelled repeat of a route at another crag is merged with it
+        let misspelled = Ascent::new(
+            Route::new(
+                "Classic Route".to_string(),
+                "5.12a".to_string(),
+                "yds".to_string(),
+                "Some Crg".to_string(),
+            )
+            .unwrap(),
+            date!(2023 - 06 - 01),
+            Style::Redpoint,
+        );
+        db.log_ascent(&misspelled).unwrap();
+
+        assert_eq!(db.merge_crags("Some Crg", "Some Crag").unwrap(), 1);
+        assert!(!db.crags().unwrap().contains(&"Some Crg".to_string()));
+        assert_eq!(db.find_ascents(&ascents()[0].route).unwrap().len(), 2);
+        assert_eq!(db.unique_route_count(&everything()).unwrap(), 12);
+
+        assert_eq!(db.merge_crags("New Crag", "Old Crag").unwrap(), 2);
+        assert_eq!(db.total_count(&everything()).unwrap(), 13);
+        assert_eq!(
+            db.crags().unwrap(),
+            ["Another Crag", "Old Crag", "Some Crag"],
+        );
+
+        assert_eq!(
+            db.merge_crags("Old Crag", "Old Crag").unwrap_err(),
+            Error::User(User::SameCrag),
+        );
+        assert_eq!(
+            db.merge_crags("New Crag", "Old Crag").unwrap_err(),
+            Error::User(User::CragNotFound("New Crag".to_string())),
+        );
+    }
+
+    #[test]
+    fn merge_crags_conflict() {
+        let db = set_up_test_db();
 
-        for valid_grade in valid_grades {
-            let result = Route::new(
-                "Some Route".to_string(),
-                valid_grade,
-                "Some Crag".to_string(),
-            );
+        let duplicate = Ascent::new(
+            Route::new(
+                "Classic Route".to_string(),
+                "5.12a".to_string(),
+                "yds".to_string(),
+                "Some Crag (North)".to_string(),
+            )
+            .unwrap(),
+            date!(2023 - 01 - 01),
+            Style::Redpoint,
+        );
+        db.log_ascent(&duplicate).unwrap();
 
-            assert!(result.is_ok());
-        }
+        assert_eq!(
+            db.merge_crags("Some Crag (North)", "Some Crag")
+                .unwrap_err(),
+            Error::User(User::CragMergeConflict(vec![
+                "Classic Route 5.12a (YDS) at Some Crag on 2023-01-01 (redpoint)".to_string(),
+            ])),
+        );
+        assert_eq!(db.total_count(&everything()).unwrap(), 13);
+        assert!(db
+            .crags()
+            .unwrap()
+            .contains(&"Some Crag (North)".to_string()));
     }
 
-    fn ascents() -> [Ascent; 8] {
-        [
-            Ascent::new(
-                Route::new(
-                    "Classic Route".to_string(),
-                    "5.12a".to_string(),
-                    "Some Crag".to_string(),
-                )
-                .unwrap(),
-                date!(2023 - 01 - 01),
-            ),
-            Ascent::new(
-                Route::new(
-                    "Some Other Route".to_string(),
-                    "5.9".to_string(),
-                    "Some Crag".to_string(),
-                )
-                .unwrap(),
-                date!(2022 - 01 - 01),
-            ),
-            Ascent::new(
-                Route::new(
-                    "New Route".to_string(),
-                    "5.10d".to_string(),
-                    "New Crag".to_string(),
-                )
-                .unwrap(),
-                date!(2022 - 01 - 01),
-            ),
-            Ascent::new(
-                Route::new(
-                    "Another Route".to_string(),
-                    "5.10a".to_string(),
-                    "Another Crag".to_string(),
-                )
-                .unwrap(),
-                date!(2023 - 01 - 01),
-            ),
-            Ascent::new(
-                Route::new(
-                    "Some Route".to_string(),
-                    "5.7".to_string(),
-                    "Some Crag".to_string(),
-                )
-                .unwrap(),
-                date!(2023 - 01 - 01),
-            ),
-            Ascent::new(
-                Route::new(
-                    "Old Route".to_string(),
-                    "5.11a".to_string(),
-                    "Old Crag".to_string(),
-                )
-                .unwrap(),
-                date!(2022 - 01 - 01),
-            ),
-            Ascent::new(
-                Route::new(
-                    "Cool Route".to_string(),
-                    "5.10a".to_string(),
-                    "Some Crag".to_string(),
-                )
-                .unwrap(),
-                date!(2022 - 01 - 01),
-            ),
-            Ascent::new(
-                Route::new(
-                    "Last Route".to_string(),
-                    "5.7".to_string(),
-                    "Old Crag".to_string(),
-                )
-                .unwrap(),
-                date!(2023 - 01 - 01),
-            ),
-        ]
-    }
+    #[test]
+    fn log_ascents() {
+        let db = set_up_test_db();
 
-    fn set_up_test_db() -> AscentDB {
-        let test_db = "test.db".to_string();
+        let new = Ascent::new(
+            Route::new(
+                "Imported Route".to_string(),
+                "5.8".to_string(),
+                "yds".to_string(),
+                "Some Crag".to_string(),
+            )
+            .unwrap(),
+            date!(2024 - 01 - 01),
+            Style::Flash,
+        );
 
-        if !utils::exists(&test_db) {
-            panic!("{test_db} must be initialized to test");
-        }
+        let mut imported = ascents().to_vec();
+        imported.push(new.clone());
+        imported.push(new.clone());
 
-        let conn = Connection::open(&test_db).unwrap();
-        conn.execute("DELETE FROM ascents", ()).unwrap();
+        assert_eq!(db.log_ascents(&imported, true).unwrap(), [&new]);
+        assert_eq!(db.total_count(&everything()).unwrap(), 12);
 
-        let db = AscentDB::new(&test_db).unwrap();
+        assert_eq!(db.log_ascents(&imported, false).unwrap(), [&new]);
+        assert_eq!(db.total_count(&everything()).unwrap(), 13);
+    }
 
-        for ascent in &ascents() {
-            db.log_ascent(ascent).unwrap();
-        }
+    #[test]
+    fn scales() {
+        let db = set_up_test_db();
 
-        db
+        let expected = vec!["yds".to_string(), "french".to_string(), "v".to_string()];
+
+        assert_eq!(db.scales(&everything()).unwrap(), expected);
     }
 
     #[test]
-    fn crags() {
+    fn normalized_grade_counts() {
         let db = set_up_test_db();
 
+        // The French 6b+ is counted as a 5.11a
         let expected = vec![
-            "Another Crag".to_string(),
-            "New Crag".to_string(),
-            "Old Crag".to_string(),
-            "Some Crag".to_string(),
+            Count {
+                category: "5.7".to_string(),
+                value: 2,
+            },
+            Count {
+                category: "5.9".to_string(),
+                value: 1,
+            },
+            Count {
+                category: "5.10a".to_string(),
+                value: 2,
+            },
+            Count {
+                category: "5.10+".to_string(),
+                value: 1,
+            },
+            Count {
+                category: "5.10d".to_string(),
+                value: 1,
+            },
+            Count {
+                category: "5.11a".to_string(),
+                value: 2,
+            },
+            Count {
+                category: "5.11a/b".to_string(),
+                value: 1,
+            },
+            Count {
+                category: "5.12a".to_string(),
+                value: 1,
+            },
         ];
 
-        assert_eq!(db.crags().unwrap(), expected);
+        assert_eq!(
+            db.normalized_grade_counts(&everything(), "yds").unwrap(),
+            expected
+        );
+
+        let expected = vec![Count {
+            category: "6B".to_string(),
+            value: 1,
+        }];
+
+        assert_eq!(
+            db.normalized_grade_counts(&everything(), "font").unwrap(),
+            expected
+        );
     }
 
     #[test]
-    fn log_ascent() {
+    fn hardest_ascent() {
         let db = set_up_test_db();
+        let ascents = ascents();
 
-        for ascent in &ascents() {
-            assert_eq!(
-                db.log_ascent(ascent).unwrap_err(),
-                Error::User(User::AscentAlreadyLogged(format_date(ascent.date))),
-            );
-        }
+        assert_eq!(
+            db.hardest_ascent(&everything(), "yds", None)
+                .unwrap()
+                .as_ref(),
+            Some(&ascents[0])
+        );
+        assert_eq!(
+            db.hardest_ascent(&everything(), "yds", Some(Style::Onsight))
+                .unwrap()
+                .as_ref(),
+            Some(&ascents[9])
+        );
+        assert_eq!(
+            db.hardest_ascent(&everything(), "v", None)
+                .unwrap()
+                .as_ref(),
+            Some(&ascents[11])
+        );
+        assert_eq!(
+            db.hardest_ascent(&everything(), "uiaa", None).unwrap(),
+            None
+        );
+        assert_eq!(
+            db.hardest_ascent(&everything(), "v", Some(Style::Onsight))
+                .unwrap(),
+            None
+        );
     }
 
     #[test]
-    fn find_ascent() {
+    fn normalized_hardest_ascent() {
         let db = set_up_test_db();
+        let ascents = ascents();
 
-        for ascent in ascents() {
-            let route = Route::new(
-                ascent.route.name.clone(),
-                ascent.route.grade.clone(),
-                ascent.route.crag.clone(),
-            )
+        let (ascent, grade) = db
+            .normalized_hardest_ascent(&everything(), "french", None)
+            .unwrap()
             .unwrap();
+        assert_eq!(ascent, ascents[0]);
+        assert_eq!(grade, "7a+");
 
-            assert_eq!(ascent, db.find_ascent(route).unwrap());
-        }
+        // The French 6b+ flash is harder than the YDS 5.10a flash
+        let (ascent, grade) = db
+            .normalized_hardest_ascent(&everything(), "yds", Some(Style::Flash))
+            .unwrap()
+            .unwrap();
+        assert_eq!(ascent, ascents[10]);
+        assert_eq!(grade, "5.11a");
 
-        let route = Route::new(
-            "Non-existent route".to_string(),
-            "5.7".to_string(),
-            "Non-existent crag".to_string(),
+        let (ascent, grade) = db
+            .normalized_hardest_ascent(&everything(), "font", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(ascent, ascents[11]);
+        assert_eq!(grade, "6B");
+    }
+
+    #[test]
+    fn style_counts() {
+        let db = set_up_test_db();
+
+        let expected = vec![
+            Count {
+                category: "onsight".to_string(),
+                value: 3,
+            },
+            Count {
+                category: "flash".to_string(),
+                value: 3,
+            },
+            Count {
+                category: "redpoint".to_string(),
+                value: 4,
+            },
+            Count {
+                category: "pinkpoint".to_string(),
+                value: 1,
+            },
+            Count {
+                category: "toprope".to_string(),
+                value: 1,
+            },
+        ];
+
+        assert_eq!(db.style_counts(&everything()).unwrap(), expected);
+    }
+
+    #[test]
+    fn resolve_route_id() {
+        let db = set_up_test_db();
+        let route = &ascents()[0].route;
+
+        let route_id = db.find_route_id(route).unwrap().unwrap();
+        assert_eq!(db.resolve_route_id(route).unwrap(), route_id);
+
+        let new_route = Route::new(
+            "Brand New Route".to_string(),
+            "5.8".to_string(),
+            "yds".to_string(),
+            "Brand New Crag".to_string(),
         )
         .unwrap();
 
-        assert_eq!(
-            db.find_ascent(route).unwrap_err(),
-            Error::User(User::AscentNotFound),
-        );
+        assert_eq!(db.find_route_id(&new_route).unwrap(), None);
+
+        let new_route_id = db.resolve_route_id(&new_route).unwrap();
+        assert_ne!(new_route_id, route_id);
+        assert_eq!(db.find_route_id(&new_route).unwrap(), Some(new_route_id));
+        assert!(db.crags().unwrap().contains(&"Brand New Crag".to_string()));
     }
 
     #[test]
-    fn drop_ascent() {
+    fn drop_ascent_cleans_up_routes_and_crags() {
         let db = set_up_test_db();
 
         for ascent in &ascents() {
-            let route = &ascent.route;
-            assert!(db.drop_ascent(route).is_ok());
-            assert_eq!(
-                db.drop_ascent(route).unwrap_err(),
-                Error::User(User::AscentNotFound),
-            );
+            db.drop_ascent(ascent).unwrap();
         }
+
+        assert_eq!(db.crags().unwrap(), Vec::<String>::new());
+        assert_eq!(db.find_route_id(&ascents()[0].route).unwrap(), None);
     }
 
     #[test]
     fn total_count() {
         let db = set_up_test_db();
-        assert_eq!(db.total_count().unwrap(), 8);
+        assert_eq!(db.total_count(&everything()).unwrap(), 12);
+    }
+
+    #[test]
+    fn unique_route_count() {
+        let db = set_up_test_db();
+        assert_eq!(db.unique_route_count(&everything()).unwrap(), 12);
     }
 
     #[test]
@@ -492,15 +2510,15 @@ mod tests {
         let expected = vec![
             Count {
                 category: "2022".to_string(),
-                value: 4,
+                value: 6,
             },
             Count {
                 category: "2023".to_string(),
-                value: 4,
+                value: 6,
             },
         ];
 
-        assert_eq!(db.year_counts().unwrap(), expected);
+        assert_eq!(db.year_counts(&everything()).unwrap(), expected);
     }
 
     #[test]
@@ -510,23 +2528,58 @@ mod tests {
         let expected = vec![
             Count {
                 category: "Another Crag".to_string(),
-                value: 1,
+                value: 2,
             },
             Count {
                 category: "New Crag".to_string(),
-                value: 1,
+                value: 2,
             },
             Count {
                 category: "Old Crag".to_string(),
-                value: 2,
+                value: 3,
             },
             Count {
                 category: "Some Crag".to_string(),
-                value: 4,
+                value: 5,
             },
         ];
 
-        assert_eq!(db.crag_counts().unwrap(), expected);
+        assert_eq!(db.crag_counts(&everything()).unwrap(), expected);
+    }
+
+    #[test]
+    fn pyramid_counts() {
+        let db = set_up_test_db();
+
+        let filter = AscentFilter {
+            grade_max: Some("5.9".to_string()),
+            ..Default::default()
+        };
+
+        // One of the two 5.7s was toproped, and 5.8 was never led at all
+        let expected = [("5.7", 1), ("5.8", 0), ("5.9", 1)]
+            .map(|(grade, count)| Count::new(grade.to_string(), count));
+
+        assert_eq!(db.pyramid_counts(&filter, "yds").unwrap(), expected);
+        assert!(db.pyramid_counts(&filter, "french").unwrap().is_empty());
+
+        // 5.10+ counts as 5.10c and 5.11a/b as 5.11a, without a level of
+        // their own
+        let filter = AscentFilter {
+            grade_min: Some("5.10a".to_string()),
+            grade_max: Some("5.11d".to_string()),
+            ..Default::default()
+        };
+        let expected = [
+            ("5.10a", 2),
+            ("5.10b", 0),
+            ("5.10c", 1),
+            ("5.10d", 1),
+            ("5.11a", 2),
+        ]
+        .map(|(grade, count)| Count::new(grade.to_string(), count));
+
+        assert_eq!(db.pyramid_counts(&filter, "yds").unwrap(), expected);
     }
 
     #[test]
@@ -546,6 +2599,10 @@ mod tests {
                 category: "5.10a".to_string(),
                 value: 2,
             },
+            Count {
+                category: "5.10+".to_string(),
+                value: 1,
+            },
             Count {
                 category: "5.10d".to_string(),
                 value: 1,
@@ -554,12 +2611,30 @@ mod tests {
                 category: "5.11a".to_string(),
                 value: 1,
             },
+            Count {
+                category: "5.11a/b".to_string(),
+                value: 1,
+            },
             Count {
                 category: "5.12a".to_string(),
                 value: 1,
             },
         ];
 
-        assert_eq!(db.grade_counts().unwrap(), expected);
+        assert_eq!(db.grade_counts(&everything(), "yds").unwrap(), expected);
+
+        let expected = vec![Count {
+            category: "6b+".to_string(),
+            value: 1,
+        }];
+
+        assert_eq!(db.grade_counts(&everything(), "french").unwrap(), expected);
+        let expected = vec![Count {
+            category: "V4".to_string(),
+            value: 1,
+        }];
+
+        assert_eq!(db.grade_counts(&everything(), "v").unwrap(), expected);
+        assert_eq!(db.grade_counts(&everything(), "uiaa").unwrap(), vec![]);
     }
 }
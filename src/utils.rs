@@ -1,12 +1,17 @@
+use crate::error::{Error, Result, User};
 use std::{
     io::{self, Write},
     path::Path,
     process,
 };
-use time::{format_description::FormatItem, macros::format_description};
+use time::{format_description::FormatItem, macros::format_description, Date};
 
 pub const DATE_FORMAT: &[FormatItem<'_>] = format_description!("[year]-[month]-[day]");
 
+pub fn parse_date(date: String) -> Result<Date> {
+    Date::parse(&date, DATE_FORMAT).map_err(|_| Error::User(User::InvalidDate))
+}
+
 pub fn input(prompt: &str) -> String {
     print!("{prompt}");
 
@@ -45,3 +50,89 @@ pub fn exists(path: &String) -> bool {
         .try_exists()
         .expect("Should be able to determine if path exists")
 }
+
+// Lowercases a name and drops everything but letters and digits, so that
+// names differing only in case, spacing or punctuation compare equal
+pub fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+// Levenshtein distance between two strings, counting chars rather than bytes
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut distances: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_diagonal + usize::from(a_char != *b_char);
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution
+                .min(distances[j] + 1)
+                .min(previous_diagonal + 1);
+        }
+    }
+
+    distances[b.len()]
+}
+
+/// Finds the names that look like a misspelling of the given one, from most
+/// to least similar
+pub fn similar_names<'a>(name: &str, names: &'a [String]) -> Vec<&'a String> {
+    let name = normalize_name(name);
+    // Allow roughly one typo for every four letters
+    let max_distance = (name.chars().count() / 4).max(1);
+
+    let mut similar: Vec<(usize, &String)> = names
+        .iter()
+        .filter_map(|other| {
+            let normalized = normalize_name(other);
+            let distance = edit_distance(&name, &normalized);
+            let contained = !name.is_empty()
+                && !normalized.is_empty()
+                && (normalized.contains(&name) || name.contains(&normalized));
+
+            (distance <= max_distance || contained).then_some((distance, other))
+        })
+        .collect();
+
+    similar.sort_by_key(|(distance, _)| *distance);
+
+    similar.into_iter().map(|(_, other)| other).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("crag", ""), 4);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("céüse", "ceuse"), 2);
+    }
+
+    #[test]
+    fn similar_crag_names() {
+        let names = [
+            "Reimers Ranch".to_string(),
+            "Reimer Ranch".to_string(),
+            "Enchanted Rock".to_string(),
+            "Red River Gorge".to_string(),
+        ];
+
+        assert_eq!(
+            similar_names("reimer's  ranch", &names),
+            [&names[0], &names[1]],
+        );
+        assert_eq!(similar_names("Enchanted", &names), [&names[2]]);
+        assert_eq!(similar_names("Rde Rivr Gorge", &names), [&names[3]]);
+        assert!(similar_names("Smith Rock", &names).is_empty());
+    }
+}